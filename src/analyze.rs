@@ -0,0 +1,125 @@
+use std::fmt::Write as _;
+
+use crate::{
+    color::{oklab_distance, ColorValue},
+    palette::push_background,
+    simulate::{Deficiency, Simulation},
+};
+
+// width of a matrix cell, enough for a two digit distance plus padding
+const CELL: usize = 4;
+
+// visions every pair is checked under, besides normal
+const DEFICIENCIES: [Deficiency; 3] = [Deficiency::Protanopia, Deficiency::Deuteranopia, Deficiency::Tritanopia];
+
+// a pair that's too close to tell apart under at least one vision
+pub struct ClosePair {
+    pub first: usize,
+    pub second: usize,
+    pub visions: Vec<(&'static str, f32)>,
+}
+
+// OKLab ΔE scaled by 100 (about 2 is a just noticeable difference) for every pair
+pub fn distance_matrix(colors: &[(u8, u8, u8)]) -> Vec<Vec<f32>> {
+    colors.iter().map(|&a| colors.iter().map(|&b| oklab_distance(a, b) * 100.0).collect()).collect()
+}
+
+// pairs closer than `min_distance` with normal vision or any of the simulated deficiencies
+pub fn close_pairs(colors: &[(u8, u8, u8)], min_distance: f32) -> Vec<ClosePair> {
+    let mut visions = vec![("normal", distance_matrix(colors))];
+    for deficiency in DEFICIENCIES {
+        let simulation = Simulation::new(deficiency, 1.0);
+        let seen: Vec<(u8, u8, u8)> = colors.iter().map(|&c| simulation.apply(c)).collect();
+        visions.push((deficiency.name(), distance_matrix(&seen)));
+    }
+
+    let mut pairs = Vec::new();
+    for first in 0..colors.len() {
+        for second in first + 1..colors.len() {
+            let close: Vec<(&'static str, f32)> = visions
+                .iter()
+                .map(|(name, matrix)| (*name, matrix[first][second]))
+                .filter(|&(_, d)| d < min_distance)
+                .collect();
+            if !close.is_empty() {
+                pairs.push(ClosePair { first, second, visions: close });
+            }
+        }
+    }
+    pairs
+}
+
+fn swatch(buf: &mut String, color: (u8, u8, u8), width: usize) {
+    push_background(buf, ColorValue::Rgb(color.0, color.1, color.2));
+    buf.push_str(&" ".repeat(width));
+    buf.push_str("\x1b[0m");
+}
+
+// the distance matrix with swatches along both axes, followed by every pair that's
+// too close and the visions it's too close under
+pub fn print_report(palette: &[(String, (u8, u8, u8))], min_distance: f32) {
+    let colors: Vec<(u8, u8, u8)> = palette.iter().map(|(_, c)| *c).collect();
+    let matrix = distance_matrix(&colors);
+    let label_width = palette.iter().map(|(l, _)| l.chars().count()).max().unwrap_or(0);
+    let mut buf = String::new();
+
+    // header: a swatch over each column
+    buf.push_str(&" ".repeat(label_width + 4));
+    for &color in &colors {
+        swatch(&mut buf, color, CELL - 1);
+        buf.push(' ');
+    }
+    buf.push('\n');
+
+    for (row, (label, color)) in palette.iter().enumerate() {
+        let _ = write!(buf, "{:>width$} ", label, width = label_width);
+        swatch(&mut buf, *color, 2);
+        buf.push(' ');
+        for (col, &d) in matrix[row].iter().enumerate() {
+            if row == col {
+                let _ = write!(buf, "{:>w$} ", "·", w = CELL - 1);
+            } else {
+                // red below the threshold, yellow below twice it
+                let style = if d < min_distance { "\x1b[1;31m" } else if d < 2.0 * min_distance { "\x1b[33m" } else { "\x1b[2m" };
+                let _ = write!(buf, "{}{:>w$.0}\x1b[0m ", style, d.min(99.0), w = CELL - 1);
+            }
+        }
+        buf.push('\n');
+    }
+
+    let pairs = close_pairs(&colors, min_distance);
+    if pairs.is_empty() {
+        let _ = writeln!(buf, "\nevery pair is at least ΔE {} apart, including with color blindness", min_distance);
+    } else {
+        let _ = writeln!(buf, "\n{} pair{} closer than ΔE {}:", pairs.len(), if pairs.len() == 1 { "" } else { "s" }, min_distance);
+        for pair in pairs {
+            let ((a_label, a), (b_label, b)) = (&palette[pair.first], &palette[pair.second]);
+            swatch(&mut buf, *a, 2);
+            swatch(&mut buf, *b, 2);
+            let visions: Vec<String> = pair.visions.iter().map(|(name, d)| format!("{} {:.1}", name, d)).collect();
+            let _ = writeln!(buf, " {} / {}: {}", a_label, b_label, visions.join(", "));
+        }
+    }
+
+    print!("{}", buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_near_duplicates_and_red_green_confusion() {
+        let colors = [(0, 0, 238), (10, 10, 240), (200, 60, 40), (90, 140, 40), (255, 255, 255)];
+        let pairs = close_pairs(&colors, 5.0);
+        let found: Vec<(usize, usize, Vec<&str>)> =
+            pairs.iter().map(|p| (p.first, p.second, p.visions.iter().map(|v| v.0).collect())).collect();
+
+        // the two blues are close for everyone
+        assert_eq!(found[0].0..found[0].1, 0..1);
+        assert_eq!(found[0].2, ["normal", "protanopia", "deuteranopia", "tritanopia"]);
+        // red and green only merge without red-green vision
+        let red_green = found.iter().find(|p| (p.0, p.1) == (2, 3)).expect("red/green flagged");
+        assert!(!red_green.2.contains(&"normal") && red_green.2.contains(&"deuteranopia"));
+    }
+}
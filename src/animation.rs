@@ -0,0 +1,578 @@
+use std::{f32::consts::{PI, TAU}, thread};
+
+use crate::ansi::ansi_to_rgb;
+use crate::color::{hsv_to_rgb, to_u8};
+use crate::rainbow::{Fade, GoalMode, PixelBuffer};
+use crate::rng::SimpleRng;
+
+//////////////////////////////////////////////////////////////////////////////////////////
+/// Procedural fullscreen animations for --crazy
+
+// dimensions of the pixel grid an animation draws into
+#[derive(Clone, Copy)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub aspect: f32, // pixel height / pixel width, ~2 for full cells and ~1 for half blocks
+    pub threads: usize, // row bands to split per-pixel work into
+}
+
+impl Grid {
+    pub fn size(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+pub trait Animation {
+    // start over on a freshly sized buffer
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng);
+
+    // advance by `dt` seconds
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng);
+
+    // advance one frame of `dt` seconds while paused, making at least one visible
+    // change even when the animation runs slower than the frame rate
+    fn step(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        self.update(pixels, grid, dt, rng);
+    }
+
+    // variant switched with the style key, if the animation has any
+    fn style(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn next_style(&mut self) {}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AnimationKind {
+    Fade,
+    Plasma,
+    Fire,
+    Wave,
+    Life,
+    Palette,
+}
+
+impl AnimationKind {
+    pub const NAMES: &'static str = "fade, plasma, fire, wave, life, palette";
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fade"    => Some(Self::Fade),
+            "plasma"  => Some(Self::Plasma),
+            "fire"    => Some(Self::Fire),
+            "wave"    => Some(Self::Wave),
+            "life"    => Some(Self::Life),
+            "palette" => Some(Self::Palette),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fade => "fade",
+            Self::Plasma => "plasma",
+            Self::Fire => "fire",
+            Self::Wave => "wave",
+            Self::Life => "life",
+            Self::Palette => "palette",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Fade => Self::Plasma,
+            Self::Plasma => Self::Fire,
+            Self::Fire => Self::Wave,
+            Self::Wave => Self::Life,
+            Self::Life => Self::Palette,
+            Self::Palette => Self::Fade,
+        }
+    }
+
+    pub fn build(self, fade_speed: f32, goal: GoalMode, scalar: bool) -> Box<dyn Animation> {
+        match self {
+            Self::Fade => Box::new(Fade::new(fade_speed, goal, scalar)),
+            Self::Plasma => Box::new(Plasma::new()),
+            Self::Fire => Box::new(Fire::new()),
+            Self::Wave => Box::new(Wave::new()),
+            Self::Life => Box::new(Life::new()),
+            Self::Palette => Box::new(Palette::new()),
+        }
+    }
+}
+
+// random angle in 0..TAU
+fn random_phase(rng: &mut SimpleRng) -> f32 {
+    rng.next_u8() as f32 / 256.0 * TAU
+}
+
+// run `f(first_row, r, g, b)` over bands of whole rows, in parallel when the grid allows it
+fn par_rows<F>(pixels: &mut PixelBuffer, grid: Grid, f: F)
+where
+    F: Fn(usize, &mut [u8], &mut [u8], &mut [u8]) + Sync,
+{
+    let size = grid.size();
+    let (r, g, b) = (&mut pixels.r[..size], &mut pixels.g[..size], &mut pixels.b[..size]);
+    if grid.threads <= 1 || grid.width == 0 {
+        f(0, r, g, b);
+        return;
+    }
+
+    let band_len = grid.height.div_ceil(grid.threads) * grid.width;
+    let f = &f;
+    thread::scope(|s| {
+        for (i, ((r, g), b)) in r.chunks_mut(band_len).zip(g.chunks_mut(band_len)).zip(b.chunks_mut(band_len)).enumerate() {
+            s.spawn(move || f(i * band_len / grid.width, r, g, b));
+        }
+    });
+}
+
+#[inline(always)]
+fn set_pixel(pixels: &mut PixelBuffer, i: usize, (r, g, b): (u8, u8, u8)) {
+    pixels.r[i] = r;
+    pixels.g[i] = g;
+    pixels.b[i] = b;
+}
+
+// runs a fixed-rate simulation from variable frame times
+struct Stepper {
+    rate: f32, // steps per second
+    accum: f32,
+}
+
+impl Stepper {
+    // upper bound so a long pause doesn't stall the next frame
+    const MAX_STEPS: u32 = 8;
+
+    fn new(rate: f32) -> Self {
+        Self { rate, accum: 0.0 }
+    }
+
+    fn steps(&mut self, dt: f32) -> u32 {
+        self.accum += self.rate * dt;
+        let whole = self.accum.floor();
+        self.accum -= whole;
+        (whole as u32).min(Self::MAX_STEPS)
+    }
+}
+
+
+// classic demoscene plasma: a sum of sine fields mapped onto phase-shifted color waves
+pub struct Plasma {
+    time: f32,
+    phases: [f32; 4],
+}
+
+impl Plasma {
+    fn new() -> Self {
+        Self { time: 0.0, phases: [0.0; 4] }
+    }
+}
+
+impl Animation for Plasma {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng) {
+        self.time = 0.0;
+        for phase in self.phases.iter_mut() {
+            *phase = random_phase(rng);
+        }
+        self.update(pixels, grid, 0.0, rng);
+    }
+
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, _: &mut SimpleRng) {
+        self.time += dt;
+        let t = self.time;
+        let [p0, p1, p2, p3] = self.phases;
+
+        // work in units of pixel width so the pattern isn't stretched vertically
+        let scale = TAU / grid.width.max(1) as f32;
+        let cx = grid.width as f32 * (0.5 + 0.3 * (t * 0.3).sin());
+        let cy = grid.height as f32 * grid.aspect * (0.5 + 0.3 * (t * 0.23).cos());
+
+        par_rows(pixels, grid, |first_row, r, g, b| {
+            for i in 0..r.len() {
+                let fx = (i % grid.width) as f32;
+                let fy = (first_row + i / grid.width) as f32 * grid.aspect;
+                let dist = ((fx - cx).powi(2) + (fy - cy).powi(2)).sqrt();
+                let v = (fx * scale * 2.0 + t + p0).sin()
+                    + (fy * scale * 3.0 - t * 0.7 + p1).sin()
+                    + ((fx + fy) * scale * 1.5 + t * 0.5 + p2).sin()
+                    + (dist * scale * 4.0 - t * 1.3 + p3).sin();
+                let a = v * PI / 2.0;
+
+                r[i] = to_u8(a.sin() * 0.5 + 0.5);
+                g[i] = to_u8((a + TAU / 3.0).sin() * 0.5 + 0.5);
+                b[i] = to_u8((a + 2.0 * TAU / 3.0).sin() * 0.5 + 0.5);
+            }
+        });
+    }
+}
+
+
+// heat propagating upwards from a burning bottom row
+pub struct Fire {
+    heat: Vec<u8>,
+    palette: Vec<(u8, u8, u8)>,
+    stepper: Stepper,
+}
+
+impl Fire {
+    const MAX_HEAT: u8 = 36;
+    const RATE: f32 = 30.0;
+    // black through red, orange and yellow to white
+    const STOPS: [(u8, u8, u8); 5] = [(7, 7, 7), (143, 39, 7), (223, 87, 7), (191, 167, 39), (255, 255, 255)];
+
+    fn new() -> Self {
+        let palette = (0..=Self::MAX_HEAT)
+            .map(|heat| {
+                let t = heat as f32 / Self::MAX_HEAT as f32 * (Self::STOPS.len() - 1) as f32;
+                let i = (t as usize).min(Self::STOPS.len() - 2);
+                let f = t - i as f32;
+                let (a, b) = (Self::STOPS[i], Self::STOPS[i + 1]);
+                let lerp = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * f).round() as u8;
+                (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+            })
+            .collect();
+
+        Self { heat: Vec::new(), palette, stepper: Stepper::new(Self::RATE) }
+    }
+
+    fn spread(&mut self, grid: Grid, rng: &mut SimpleRng) {
+        let w = grid.width;
+        // average heat lost per row, so flames reach roughly 60% of the screen whatever its height
+        let cooling = Self::MAX_HEAT as f32 / (grid.height as f32 * 0.6);
+        let base_decay = cooling.floor() as u8;
+        let extra_threshold = (cooling.fract() * 255.0) as u8;
+
+        for y in 1..grid.height {
+            for x in 0..w {
+                let drift = (rng.next_u8() & 3) as usize; // moves the heat 1 right to 2 left
+                let dx = (x + 1).saturating_sub(drift).min(w - 1);
+                let decay = base_decay + u8::from(rng.next_u8() < extra_threshold);
+                self.heat[(y - 1) * w + dx] = self.heat[y * w + x].saturating_sub(decay);
+            }
+        }
+    }
+}
+
+impl Animation for Fire {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, _: &mut SimpleRng) {
+        self.heat = vec![0; grid.size()];
+        if grid.height > 0 {
+            let bottom = (grid.height - 1) * grid.width;
+            self.heat[bottom..].fill(Self::MAX_HEAT);
+        }
+        for i in 0..grid.size() {
+            set_pixel(pixels, i, self.palette[self.heat[i] as usize]);
+        }
+    }
+
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        for _ in 0..self.stepper.steps(dt) {
+            self.spread(grid, rng);
+        }
+        for i in 0..grid.size() {
+            set_pixel(pixels, i, self.palette[self.heat[i] as usize]);
+        }
+    }
+
+    fn step(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        self.update(pixels, grid, dt.max(1.0 / Self::RATE), rng);
+    }
+}
+
+
+// full-saturation rainbow scrolling sideways with a vertical wobble
+pub struct Wave {
+    time: f32,
+    phase: f32,
+}
+
+impl Wave {
+    const SPEED: f32 = 0.2; // screen widths per second
+
+    fn new() -> Self {
+        Self { time: 0.0, phase: 0.0 }
+    }
+}
+
+impl Animation for Wave {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng) {
+        self.time = 0.0;
+        self.phase = random_phase(rng);
+        self.update(pixels, grid, 0.0, rng);
+    }
+
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, _: &mut SimpleRng) {
+        self.time += dt;
+        let (time, phase) = (self.time, self.phase);
+        let w = grid.width.max(1) as f32;
+
+        par_rows(pixels, grid, |first_row, r, g, b| {
+            for (row, start) in (0..r.len()).step_by(grid.width).enumerate() {
+                let fy = (first_row + row) as f32 * grid.aspect;
+                let wobble = 0.08 * (fy / w * TAU * 2.0 + time * 2.0 + phase).sin();
+                for x in 0..grid.width {
+                    let hue = x as f32 / w + fy / w * 0.25 + wobble - time * Self::SPEED;
+                    (r[start + x], g[start + x], b[start + x]) = hsv_to_rgb(hue * 360.0, 1.0, 1.0);
+                }
+            }
+        });
+    }
+}
+
+
+// Conway's Game of Life where newborn cells inherit a slightly mutated parent color
+pub struct Life {
+    alive: Vec<bool>,
+    next: Vec<bool>,
+    colors: PixelBuffer,
+    stepper: Stepper,
+    history: [u64; 2], // hashes of the previous generations, to spot still lifes and blinkers
+    stale: u32,
+}
+
+impl Life {
+    const RATE: f32 = 12.0; // generations per second
+    const DENSITY: u8 = 77; // out of 256, ~30% alive on reset
+    const MUTATION: u8 = 24;
+    const MAX_STALE: u32 = 36;
+    const TRAIL: f32 = 0.02; // fraction of a dead cell's color left after one second
+
+    fn new() -> Self {
+        Self {
+            alive: Vec::new(),
+            next: Vec::new(),
+            colors: PixelBuffer::new(0),
+            stepper: Stepper::new(Self::RATE),
+            history: [0; 2],
+            stale: 0,
+        }
+    }
+
+    fn populate(&mut self, rng: &mut SimpleRng) {
+        for cell in self.alive.iter_mut() {
+            *cell = rng.next_u8() < Self::DENSITY;
+        }
+        self.colors.fill_random(rng);
+        self.history = [0; 2];
+        self.stale = 0;
+    }
+
+    fn mutate(channel: u8, rng: &mut SimpleRng) -> u8 {
+        let offset = rng.next_u8() % (2 * Self::MUTATION + 1);
+        channel.saturating_add(offset).saturating_sub(Self::MUTATION)
+    }
+
+    fn generation(&mut self, grid: Grid, rng: &mut SimpleRng) {
+        let (w, h) = (grid.width, grid.height);
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut population = 0;
+
+        for y in 0..h {
+            for x in 0..w {
+                let i = y * w + x;
+                let mut neighbors = 0u64;
+                let mut parent = i;
+                // reservoir-sample a parent among the live neighbors
+                for dy in [h - 1, 0, 1] {
+                    for dx in [w - 1, 0, 1] {
+                        if dx == 0 && dy == 0 {
+                            continue;
+                        }
+                        let n = (y + dy) % h * w + (x + dx) % w;
+                        if self.alive[n] {
+                            neighbors += 1;
+                            if rng.next_u64().is_multiple_of(neighbors) {
+                                parent = n;
+                            }
+                        }
+                    }
+                }
+
+                let lives = matches!((self.alive[i], neighbors), (true, 2) | (_, 3));
+                if lives && !self.alive[i] {
+                    self.colors.r[i] = Self::mutate(self.colors.r[parent], rng);
+                    self.colors.g[i] = Self::mutate(self.colors.g[parent], rng);
+                    self.colors.b[i] = Self::mutate(self.colors.b[parent], rng);
+                }
+                self.next[i] = lives;
+
+                if lives {
+                    population += 1;
+                    hash = (hash ^ i as u64).wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+        std::mem::swap(&mut self.alive, &mut self.next);
+
+        if self.history.contains(&hash) {
+            self.stale += 1;
+        } else {
+            self.stale = 0;
+        }
+        self.history = [self.history[1], hash];
+
+        if self.stale > Self::MAX_STALE || population < grid.size() / 50 {
+            self.populate(rng);
+        }
+    }
+}
+
+impl Animation for Life {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng) {
+        self.alive = vec![false; grid.size()];
+        self.next = vec![false; grid.size()];
+        self.colors = PixelBuffer::new(grid.size());
+        self.populate(rng);
+
+        for i in 0..grid.size() {
+            let color = if self.alive[i] { (self.colors.r[i], self.colors.g[i], self.colors.b[i]) } else { (0, 0, 0) };
+            set_pixel(pixels, i, color);
+        }
+    }
+
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        if grid.width == 0 || grid.height == 0 {
+            return;
+        }
+        for _ in 0..self.stepper.steps(dt) {
+            self.generation(grid, rng);
+        }
+
+        // dead cells leave a fading trail
+        let keep = Self::TRAIL.powf(dt);
+        for i in 0..grid.size() {
+            if self.alive[i] {
+                set_pixel(pixels, i, (self.colors.r[i], self.colors.g[i], self.colors.b[i]));
+            } else {
+                let fade = |c: u8| (c as f32 * keep) as u8;
+                set_pixel(pixels, i, (fade(pixels.r[i]), fade(pixels.g[i]), fade(pixels.b[i])));
+            }
+        }
+    }
+
+    fn step(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        self.update(pixels, grid, dt.max(1.0 / Self::RATE), rng);
+    }
+}
+
+
+// smooth random field indexing into the 256 ANSI colors, drifting through the palette
+pub struct Palette {
+    field: Vec<f32>,
+    offset: f32,
+}
+
+impl Palette {
+    const SPEED: f32 = 24.0; // palette indices per second
+
+    fn new() -> Self {
+        Self { field: Vec::new(), offset: 0.0 }
+    }
+}
+
+impl Animation for Palette {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng) {
+        let w = grid.width.max(1) as f32;
+        let waves: Vec<(f32, f32, f32)> = (0..3)
+            .map(|_| (1.0 + (rng.next_u8() % 3) as f32, 1.0 + (rng.next_u8() % 3) as f32, random_phase(rng)))
+            .collect();
+
+        self.offset = 0.0;
+        self.field = (0..grid.size())
+            .map(|i| {
+                let x = (i % grid.width) as f32 / w * TAU;
+                let y = (i / grid.width) as f32 * grid.aspect / w * TAU;
+                let v: f32 = waves.iter().map(|(fx, fy, p)| (x * fx + y * fy + p).sin()).sum();
+                // -3..=3 spread over the palette twice
+                (v + 3.0) / 6.0 * 512.0
+            })
+            .collect();
+        self.update(pixels, grid, 0.0, rng);
+    }
+
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, _: &mut SimpleRng) {
+        self.offset = (self.offset + dt * Self::SPEED) % 256.0;
+        let (field, offset) = (&self.field, self.offset);
+        par_rows(pixels, grid, |first_row, r, g, b| {
+            let start = first_row * grid.width;
+            for i in 0..r.len() {
+                let index = (field[start + i] + offset) as u32 % 256;
+                (r[i], g[i], b[i]) = ansi_to_rgb(index as u8);
+            }
+        });
+    }
+}
+
+// End of procedural fullscreen animations
+//////////////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: Grid = Grid { width: 37, height: 11, aspect: 2.0, threads: 1 };
+
+    // FNV-1a over all three channels after a fixed number of fixed-length frames
+    fn checksum(kind: AnimationKind, seed: u64) -> u64 {
+        let mut rng = SimpleRng::with_seed(seed);
+        let mut pixels = PixelBuffer::new(GRID.size());
+        let mut animation = kind.build(50.0, GoalMode::Random, false);
+
+        animation.reset(&mut pixels, GRID, &mut rng);
+        for _ in 0..20 {
+            animation.update(&mut pixels, GRID, 0.05, &mut rng);
+        }
+
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for &byte in pixels.r.iter().chain(&pixels.g).chain(&pixels.b) {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    #[test]
+    fn seed_pins_every_animation() {
+        let kinds = [
+            AnimationKind::Fade,
+            AnimationKind::Plasma,
+            AnimationKind::Fire,
+            AnimationKind::Wave,
+            AnimationKind::Life,
+            AnimationKind::Palette,
+        ];
+        let expected: [u64; 6] = [
+            17658928473246796231,
+            11943348506208442964,
+            6691134895206253247,
+            5842721714580330365,
+            4202214448509506451,
+            9012297376235880648,
+        ];
+        for (kind, expected) in kinds.into_iter().zip(expected) {
+            assert_eq!(checksum(kind, 1234), expected, "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn scalar_fade_matches_simd() {
+        let mut pixels = [PixelBuffer::new(GRID.size()), PixelBuffer::new(GRID.size())];
+        for (pixels, scalar) in pixels.iter_mut().zip([false, true]) {
+            let mut rng = SimpleRng::with_seed(99);
+            let mut fade = Fade::new(50.0, GoalMode::Near, scalar);
+            fade.reset(pixels, GRID, &mut rng);
+            for _ in 0..20 {
+                fade.update(pixels, GRID, 0.05, &mut rng);
+            }
+        }
+        assert!(pixels[0].r == pixels[1].r && pixels[0].g == pixels[1].g && pixels[0].b == pixels[1].b);
+    }
+
+    #[test]
+    fn seeds_differ() {
+        assert_ne!(checksum(AnimationKind::Fade, 1), checksum(AnimationKind::Fade, 2));
+        assert_ne!(checksum(AnimationKind::Life, 1), checksum(AnimationKind::Life, 2));
+    }
+}
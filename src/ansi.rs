@@ -1,8 +1,156 @@
 use std::{
+    fmt::Write as _,
     io::{self, BufWriter, Write},
     str,
 };
 
+use crate::{graphics, simulate};
+
+// xterm's default values for the 16 system colors
+const SYSTEM_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),       (205, 0, 0),     (0, 205, 0),     (205, 205, 0),
+    (0, 0, 238),     (205, 0, 205),   (0, 205, 205),   (229, 229, 229),
+    (127, 127, 127), (255, 0, 0),     (0, 255, 0),     (255, 255, 0),
+    (92, 92, 255),   (255, 0, 255),   (0, 255, 255),   (255, 255, 255),
+];
+
+// channel values of the 6x6x6 color cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(Self::TrueColor),
+            "256" => Some(Self::Ansi256),
+            "16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TrueColor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::TrueColor => Self::Ansi256,
+            Self::Ansi256 => Self::Ansi16,
+            Self::Ansi16 => Self::TrueColor,
+        }
+    }
+}
+
+const SYSTEM_NAMES: [&str; 16] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    "bright_black", "bright_red", "bright_green", "bright_yellow",
+    "bright_blue", "bright_magenta", "bright_cyan", "bright_white",
+];
+
+// name accepted by `named_color_to_ansi` for a system color
+pub fn ansi_name(color: u8) -> Option<&'static str> {
+    SYSTEM_NAMES.get(color as usize).copied()
+}
+
+// RGB value of an ANSI color as xterm draws it by default
+pub fn ansi_to_rgb(color: u8) -> (u8, u8, u8) {
+    match color {
+        0..=15 => SYSTEM_COLORS[color as usize],
+        16..=231 => {
+            let i = color - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        },
+        232..=255 => {
+            let v = 8 + (color - 232) * 10;
+            (v, v, v)
+        },
+    }
+}
+
+fn distance_sq(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn nearest_cube_level(v: u8) -> usize {
+    match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => ((v - 35) / 40) as usize,
+    }
+}
+
+// closest color in the 6x6x6 cube or the grayscale ramp
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (nearest_cube_level(r), nearest_cube_level(g), nearest_cube_level(b));
+    let cube = 16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8;
+
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = if avg < 8 { 232 } else { 232 + ((avg - 8) / 10).min(23) as u8 };
+
+    if distance_sq((r, g, b), ansi_to_rgb(gray)) < distance_sq((r, g, b), ansi_to_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+// closest of the 16 system colors
+pub fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> u8 {
+    (0..16u8)
+        .min_by_key(|&c| distance_sq((r, g, b), SYSTEM_COLORS[c as usize]))
+        .unwrap_or(0)
+}
+
+// SGR sequence setting the background (or foreground) to an exact color, as seen
+// through --simulate
+pub fn rgb_sgr(foreground: bool, r: u8, g: u8, b: u8) -> String {
+    let (r, g, b) = simulate::apply((r, g, b));
+    format!("\x1b[{};2;{};{};{}m", if foreground { 38 } else { 48 }, r, g, b)
+}
+
+// SGR sequence for a palette index; simulating resolves it through xterm's default
+// palette, since the transform needs the actual color
+pub fn ansi_sgr(foreground: bool, code: u8) -> String {
+    if simulate::is_active() {
+        let (r, g, b) = ansi_to_rgb(code);
+        rgb_sgr(foreground, r, g, b)
+    } else {
+        format!("\x1b[{};5;{}m", if foreground { 38 } else { 48 }, code)
+    }
+}
+
+// append the SGR sequence setting the background (or foreground) to a color at the given depth
+pub fn push_color(buf: &mut String, depth: ColorDepth, foreground: bool, r: u8, g: u8, b: u8) {
+    let (r, g, b) = simulate::apply((r, g, b));
+    let _ = match depth {
+        ColorDepth::TrueColor => write!(buf, "\x1b[{};2;{};{};{}m", if foreground { 38 } else { 48 }, r, g, b),
+        ColorDepth::Ansi256 => write!(buf, "\x1b[{};5;{}m", if foreground { 38 } else { 48 }, rgb_to_ansi256(r, g, b)),
+        ColorDepth::Ansi16 => {
+            let c = rgb_to_ansi16(r, g, b);
+            let base = match (foreground, c < 8) {
+                (true, true) => 30,
+                (true, false) => 90 - 8,
+                (false, true) => 40,
+                (false, false) => 100 - 8,
+            };
+            write!(buf, "\x1b[{}m", base + c)
+        },
+    };
+}
+
 pub fn named_color_to_ansi(input: &str) -> Option<u8> {
     match input.to_lowercase().as_str() {
         "black"                             => Some(0),
@@ -21,18 +169,23 @@ pub fn named_color_to_ansi(input: &str) -> Option<u8> {
         "bright_magenta"                    => Some(13),
         "bright_cyan"                       => Some(14),
         "bright_white"                      => Some(15),
-        "orange"                            =>  {
-            eprintln!("⚠️  Orange is not an official ANSI color; printing approximation (208).");
-            Some(208)
-        },
-        "purple"                            =>  {
-            eprintln!("⚠️  Purple is not an official ANSI color; printing approximation (129).");
-            Some(129)
-        },
+        "orange"                            => Some(208),
+        "purple"                            => Some(129),
         _                                   => None
     }
 }
 
+// orange and purple aren't system colors, which is worth saying when one is shown on
+// its own (but not for every match while scanning text)
+pub fn warn_if_approximated(input: &str, code: u8) {
+    let name = match input.to_lowercase().as_str() {
+        "orange" => "Orange",
+        "purple" => "Purple",
+        _ => return,
+    };
+    eprintln!("⚠️  {} is not an official ANSI color; printing approximation ({}).", name, code);
+}
+
 pub fn print_block_ansi(color: u8, width: u8, numbered: bool) {
     let stdout = io::stdout();
     let mut out = BufWriter::new(stdout.lock());
@@ -40,8 +193,14 @@ pub fn print_block_ansi(color: u8, width: u8, numbered: bool) {
     if numbered {
         let _ = write!(out, "{} ", color);
     }
+    if let Some(protocol) = graphics::protocol() {
+        let _ = out.flush();
+        drop(out);
+        graphics::print_block(protocol, ansi_to_rgb(color), width.into(), 1);
+        return;
+    }
 
-    let _ = write!(out, "\x1b[48;5;{}m", color);
+    let _ = write!(out, "{}", ansi_sgr(false, color));
     for _ in 0..width {
         let _ = write!(out, " ");
     }
@@ -68,7 +227,7 @@ pub fn print_blocks_ansi(color1: u8, color2: u8, width: u8, inline: bool, number
             if numbered {
                 let _ = write!(out, "\x1b[0m{}:", color);
             }
-            let _ = write!(out, "\x1b[48;5;{}m{}", color, space_block);
+            let _ = write!(out, "{}{}", ansi_sgr(false, color), space_block);
         }
         let _ = writeln!(out, "\x1b[0m");
     } else {
@@ -81,7 +240,7 @@ pub fn print_blocks_ansi(color1: u8, color2: u8, width: u8, inline: bool, number
                     let _ = write!(out, " ");
                 }
             }
-            let _ = write!(out, "\x1b[48;5;{}m{}", color, space_block);
+            let _ = write!(out, "{}{}", ansi_sgr(false, color), space_block);
             let _ = writeln!(out, "\x1b[0m");
         }
     }
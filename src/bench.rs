@@ -0,0 +1,90 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::animation::AnimationKind;
+use crate::rainbow::{Buffer, CrazyOptions};
+
+// discards everything written to it, keeping count
+struct CountingSink {
+    bytes: u64,
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct BenchResult {
+    tick: Duration,
+    render: Duration,
+    bytes: u64,
+}
+
+fn run(opts: &CrazyOptions, width: u16, height: u16, frames: u32) -> io::Result<BenchResult> {
+    let mut buffer = Buffer::with_size(opts, width, height);
+    let mut sink = CountingSink { bytes: 0 };
+    let dt = 1.0 / opts.fps.max(1) as f32;
+    let mut result = BenchResult { tick: Duration::ZERO, render: Duration::ZERO, bytes: 0 };
+
+    for _ in 0..frames {
+        let start = Instant::now();
+        buffer.advance(dt);
+        let ticked = Instant::now();
+        buffer.render(&mut sink)?;
+        result.tick += ticked - start;
+        result.render += ticked.elapsed();
+    }
+    result.bytes = sink.bytes;
+    Ok(result)
+}
+
+// time `frames` ticks and renders of a `width` x `height` grid without touching the terminal
+pub fn benchmark(opts: &CrazyOptions, width: u16, height: u16, frames: u32) -> io::Result<()> {
+    let frames = frames.max(1);
+    // only the fade has a separate SIMD path worth comparing
+    let runs: &[(&str, bool)] = if opts.animation == AnimationKind::Fade {
+        &[("simd", false), ("scalar", true)]
+    } else {
+        &[("run", opts.scalar)]
+    };
+
+    let threads = match opts.threads {
+        0 => "auto".to_string(),
+        n => n.to_string(),
+    };
+    println!(
+        "benchmark: {} on a {}x{} grid, {} frames at {} fps, {} thread(s), seed {}",
+        opts.animation.name(), width, height, frames, opts.fps, threads, opts.seed
+    );
+    println!("{:<8}{:>12}{:>12}{:>12}{:>14}", "", "ticks/s", "tick ms", "render ms", "bytes/frame");
+
+    let mut ticks_per_sec = Vec::new();
+    for &(label, scalar) in runs {
+        let opts = CrazyOptions { scalar, ..opts.clone() };
+        let result = run(&opts, width, height, frames)?;
+
+        let tick_secs = result.tick.as_secs_f64().max(f64::EPSILON);
+        ticks_per_sec.push(frames as f64 / tick_secs);
+        println!(
+            "{:<8}{:>12.1}{:>12.3}{:>12.3}{:>14}",
+            label,
+            frames as f64 / tick_secs,
+            tick_secs * 1000.0 / frames as f64,
+            result.render.as_secs_f64() * 1000.0 / frames as f64,
+            result.bytes / frames as u64,
+        );
+    }
+
+    if let [simd, scalar] = ticks_per_sec[..] {
+        println!("simd speedup: {:.2}x ({} lanes)", simd / scalar, crate::rainbow::LANES);
+    }
+    Ok(())
+}
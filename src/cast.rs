@@ -0,0 +1,250 @@
+use std::{
+    fs::File,
+    io::{self, stdout, BufRead, BufReader, BufWriter, Write},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::terminal::{clear_screen, disable_raw_mode, enable_raw_mode, restore_from_signal, set_signal_handler, terminal_size, InputHandler, Key};
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// asciicast v2 recording and playback (https://docs.asciinema.org/manual/asciicast/v2/)
+
+// forwards output to `inner` and logs everything written between flushes as one event,
+// preceded by a resize event when the terminal changed size since the last one
+pub struct CastWriter<W: Write> {
+    inner: W,
+    cast: BufWriter<File>,
+    pending: Vec<u8>,
+    start: Instant,
+    size: (u16, u16),
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn create(path: &str, inner: W, width: u16, height: u16, title: &str) -> io::Result<Self> {
+        let mut cast = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let term = std::env::var("TERM").unwrap_or_default();
+        writeln!(
+            cast,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"title\": \"{}\", \"env\": {{\"TERM\": \"{}\"}}}}",
+            width, height, timestamp, escape_json(title), escape_json(&term)
+        )?;
+        Ok(Self { inner, cast, pending: Vec::new(), start: Instant::now(), size: (width, height) })
+    }
+}
+
+impl<W: Write> Write for CastWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if !self.pending.is_empty() {
+            let time = self.start.elapsed().as_secs_f64();
+            if let Ok(size) = terminal_size()
+                && size != self.size
+            {
+                writeln!(self.cast, "[{:.6}, \"r\", \"{}x{}\"]", time, size.0, size.1)?;
+                self.size = size;
+            }
+            let data = String::from_utf8_lossy(&self.pending);
+            writeln!(self.cast, "[{:.6}, \"o\", \"{}\"]", time, escape_json(&data))?;
+            self.pending.clear();
+        }
+        self.cast.flush()
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// just enough JSON to read event lines: `[number, "string", "string"]`
+struct JsonCursor<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> JsonCursor<'a> {
+    fn new(s: &'a str) -> Self {
+        Self { chars: s.chars().peekable() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+        (self.chars.next()? == expected).then_some(())
+    }
+
+    fn number(&mut self) -> Option<f64> {
+        self.skip_whitespace();
+        let mut s = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')) {
+            s.push(c);
+        }
+        s.parse().ok()
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let hex: String = (0..4).map(|_| self.chars.next()).collect::<Option<_>>()?;
+        u32::from_str_radix(&hex, 16).ok()
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(s),
+                '\\' => match self.chars.next()? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let mut code = self.hex4()?;
+                        // surrogate pair
+                        if (0xd800..0xdc00).contains(&code) {
+                            self.expect('\\')?;
+                            self.expect('u')?;
+                            let low = self.hex4()?;
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                        }
+                        s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    },
+                    c => s.push(c), // \" \\ \/
+                },
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+// (time, type, data) of one event line
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    let mut cursor = JsonCursor::new(line);
+    cursor.expect('[')?;
+    let time = cursor.number()?;
+    cursor.expect(',')?;
+    let kind = cursor.string()?;
+    cursor.expect(',')?;
+    let data = cursor.string()?;
+    cursor.expect(']')?;
+    Some((time, kind, data))
+}
+
+// pull an integer field out of the header line
+fn header_field(header: &str, name: &str) -> Option<u16> {
+    let rest = &header[header.find(&format!("\"{}\"", name))? + name.len() + 2..];
+    let mut cursor = JsonCursor::new(rest);
+    cursor.expect(':')?;
+    cursor.number().map(|n| n as u16)
+}
+
+extern "C" fn replay_signal_handler(_: libc::c_int) {
+    restore_from_signal();
+    unsafe { libc::_exit(0) }
+}
+
+// play a recording back at its original timing, `q` or escape stops early
+pub fn replay(path: &str) -> io::Result<()> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    if !header.contains("\"version\": 2") && !header.contains("\"version\":2") {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an asciicast v2 file"));
+    }
+
+    if let (Some(w), Some(h), Ok((tw, th))) = (header_field(&header, "width"), header_field(&header, "height"), terminal_size())
+        && (tw < w || th < h)
+    {
+        eprintln!("⚠️  Recorded at {}x{}, this terminal is {}x{}; playback may look garbled.", w, h, tw, th);
+        thread::sleep(Duration::from_secs(2));
+    }
+
+    set_signal_handler(replay_signal_handler);
+    enable_raw_mode()?;
+    clear_screen()?;
+
+    let input = InputHandler::new();
+    let mut out = BufWriter::new(stdout());
+    let start = Instant::now();
+
+    let result = (|| -> io::Result<()> {
+        for (number, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((time, kind, data)) = parse_event(&line) else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("malformed event on line {}", number + 2)));
+            };
+            if kind != "o" {
+                continue;
+            }
+
+            // sleep in small slices so quitting stays responsive through long pauses
+            let due = Duration::from_secs_f64(time.max(0.0));
+            loop {
+                if input.read_keys().iter().any(|k| matches!(k, Key::Char('q') | Key::Escape | Key::CtrlC)) {
+                    return Ok(());
+                }
+                let now = start.elapsed();
+                if now >= due {
+                    break;
+                }
+                thread::sleep((due - now).min(Duration::from_millis(50)));
+            }
+
+            out.write_all(data.as_bytes())?;
+            out.flush()?;
+        }
+        Ok(())
+    })();
+
+    disable_raw_mode()?;
+    println!();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_round_trip_through_json() {
+        let data = "say \"hi\" \\ \x1b[31m\r\n\t\u{1}é🌈";
+        let line = format!("[1.250000, \"o\", \"{}\"]", escape_json(data));
+        assert_eq!(parse_event(&line), Some((1.25, "o".to_string(), data.to_string())));
+    }
+
+    #[test]
+    fn reads_unicode_escapes_and_rejects_malformed_lines() {
+        let line = r#"[0.5, "o", "\u00e9\ud83c\udf08\/"]"#;
+        assert_eq!(parse_event(line), Some((0.5, "o".to_string(), "é🌈/".to_string())));
+        assert_eq!(parse_event(r#"[1, "o""#), None);
+        assert_eq!(parse_event(r#"[1, "o", "unterminated]"#), None);
+        assert_eq!(parse_event(r#"["1", "o", "x"]"#), None);
+    }
+}
+
+// End of asciicast recording and playback
+//////////////////////////////////////////////////////////////////////////////////////////
@@ -1,9 +1,21 @@
-use std::{env,str};
+use std::{env,str,str::FromStr};
 
 use crate::{
-    ansi::{named_color_to_ansi, print_block_ansi, print_blocks_ansi}, 
-    hex::{print_block_hex, print_hex_gradient}, 
-    validate::is_valid_hex_color
+    animation::AnimationKind,
+    colorize::Direction,
+    export::ExportFormat,
+    color::{parse_color, ColorValue},
+    easing::Easing,
+    gradient::{Gradient, GradientShape},
+    graphics::GraphicsMode,
+    harmony::Harmony,
+    ansi::{ansi_to_rgb, print_block_ansi, warn_if_approximated, print_blocks_ansi, ColorDepth}, 
+    hex::{print_block_hex, print_hex_gradient, StripOptions}, 
+    palette::{AxisOrder, LabelKind},
+    rainbow::{GoalMode, GraySteps, HuePath},
+    simulate::Deficiency,
+    rng::SimpleRng,
+    view::Dither
 };
 
 
@@ -12,10 +24,67 @@ pub struct Args {
     pub inline: bool,
     pub numbered: bool,
     pub fit: bool,
+    pub fill: bool,
+    pub sample: Option<usize>,
+    pub swatches: bool,
     pub values: Vec<String>,
     pub rainbow: bool,
     pub grayscale: bool,
+    pub hue_path: HuePath,
+    pub saturation: f32,
+    pub lightness: Option<f32>,
+    pub cycles: f32,
+    pub reverse: bool,
+    pub gray_steps: GraySteps,
     pub crazy: bool,
+    pub fps: u32,
+    pub fade_speed: f32,
+    pub goal: GoalMode,
+    pub animation: AnimationKind,
+    pub seed: Option<u64>,
+    pub scalar: bool,
+    pub threads: usize,
+    pub stats: bool,
+    pub bench: bool,
+    pub frames: u32,
+    pub size: Option<(u16, u16)>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub files: Vec<String>,
+    pub unique: bool,
+    pub grid: bool,
+    pub columns: usize,
+    pub cell_height: u8,
+    pub label: LabelKind,
+    pub chart: bool,
+    pub text: bool,
+    pub highlight: bool,
+    pub harmonies: Vec<Harmony>,
+    pub export: bool,
+    pub theme: bool,
+    pub ramp: bool,
+    pub steps: usize,
+    pub name: String,
+    pub simulate: Option<Deficiency>,
+    pub severity: f32,
+    pub compare: bool,
+    pub analyze: bool,
+    pub min_distance: f32,
+    pub extract: Option<String>,
+    pub colors: usize,
+    pub view: Option<String>,
+    pub depth: ColorDepth,
+    pub dither: Dither,
+    pub graphics: GraphicsMode,
+    pub easing: Easing,
+    pub hint: Option<f32>,
+    pub contrast: f32,
+    pub format: Option<ExportFormat>,
+    pub palette: Option<Gradient>,
+    pub direction: Direction,
+    pub animate: bool,
+    pub duration: f32,
+    pub axes: AxisOrder,
     pub help: bool,
     pub version: bool,
     pub error: bool
@@ -29,10 +98,67 @@ pub fn parse_args() -> Args {
         inline: false,
         numbered: false,
         fit: false,
+        fill: false,
+        sample: None,
+        swatches: false,
         values: Vec::new(),
         rainbow: false,
         grayscale: false,
+        hue_path: HuePath::Hsv,
+        saturation: 1.0,
+        lightness: None,
+        cycles: 1.0,
+        reverse: false,
+        gray_steps: GraySteps::Gamma,
         crazy: false,
+        fps: 50,          // with one step per frame, matches the old fixed 20ms loop
+        fade_speed: 50.0,
+        goal: GoalMode::Random,
+        animation: AnimationKind::Fade,
+        seed: None,
+        scalar: false,
+        threads: 0,
+        stats: false,
+        bench: false,
+        frames: 300,
+        size: None,
+        record: None,
+        replay: None,
+        files: Vec::new(),
+        unique: false,
+        grid: false,
+        columns: 0,
+        cell_height: 1,
+        label: LabelKind::None,
+        chart: false,
+        text: false,
+        highlight: false,
+        harmonies: Vec::new(),
+        export: false,
+        theme: false,
+        ramp: false,
+        steps: 11,
+        name: "color".to_string(),
+        simulate: None,
+        severity: 1.0,
+        compare: false,
+        analyze: false,
+        min_distance: 8.0,
+        extract: None,
+        colors: 8,
+        view: None,
+        depth: ColorDepth::TrueColor,
+        dither: Dither::None,
+        graphics: GraphicsMode::Blocks,
+        easing: Easing::Linear,
+        hint: None,
+        contrast: 4.5,
+        format: None,
+        palette: None,
+        direction: Direction::Horizontal,
+        animate: false,
+        duration: 0.5,
+        axes: AxisOrder::RGB,
         help: false,
         version: false,
         error: false
@@ -66,15 +192,588 @@ pub fn parse_args() -> Args {
             "-f" | "--fit" => {
                 parsed_args.fit = true;
             },
+            "--fill" => {
+                parsed_args.fill = true;
+            },
+            "--sample" => {
+                match parse_value::<usize>(&args, &mut i, "sample") {
+                    Some(count) if count >= 2 => parsed_args.sample = Some(count),
+                    Some(count) => {
+                        eprintln!("Error: Invalid sample value `{}` (at least 2)", count);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--swatches" => {
+                parsed_args.swatches = true;
+            },
             "-r" | "--rainbow" => {
                 parsed_args.rainbow = true;
             },
             "-g" | "--grayscale" => {
                 parsed_args.grayscale = true;
             },
+            "--hue-path" => {
+                match parse_value::<String>(&args, &mut i, "hue path").map(|p| (HuePath::from_name(&p), p)) {
+                    Some((Some(path), _)) => parsed_args.hue_path = path,
+                    Some((None, p)) => {
+                        eprintln!("Error: Unknown hue path `{}` (expected hsv, hsl or oklch)", p);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--saturation" => {
+                match parse_value::<f32>(&args, &mut i, "saturation") {
+                    Some(saturation) if (0.0..=1.0).contains(&saturation) => parsed_args.saturation = saturation,
+                    Some(saturation) => {
+                        eprintln!("Error: Invalid saturation value `{}` (from 0 to 1)", saturation);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--lightness" => {
+                match parse_value::<f32>(&args, &mut i, "lightness") {
+                    Some(lightness) if (0.0..=1.0).contains(&lightness) => parsed_args.lightness = Some(lightness),
+                    Some(lightness) => {
+                        eprintln!("Error: Invalid lightness value `{}` (from 0 to 1)", lightness);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--cycles" => {
+                match parse_value::<f32>(&args, &mut i, "cycles") {
+                    Some(cycles) if cycles.is_finite() && cycles > 0.0 => parsed_args.cycles = cycles,
+                    Some(cycles) => {
+                        eprintln!("Error: Invalid cycles value `{}` (a finite number more than 0)", cycles);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--reverse" => {
+                parsed_args.reverse = true;
+            },
+            "--gray-steps" => {
+                match parse_value::<String>(&args, &mut i, "gray steps").map(|s| (GraySteps::from_name(&s), s)) {
+                    Some((Some(steps), _)) => parsed_args.gray_steps = steps,
+                    Some((None, s)) => {
+                        eprintln!("Error: Unknown gray steps `{}` (expected gamma, linear or perceptual)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
             "--crazy" => {
                 parsed_args.crazy = true;
             },
+            "--fps" => {
+                match parse_value::<u32>(&args, &mut i, "fps") {
+                    Some(fps) if fps > 0 => parsed_args.fps = fps,
+                    Some(_) => {
+                        eprintln!("Error: fps must be greater than 0");
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--fade-speed" => {
+                match parse_value::<f32>(&args, &mut i, "fade speed") {
+                    Some(speed) if speed.is_finite() && speed >= 0.0 => parsed_args.fade_speed = speed,
+                    Some(_) => {
+                        eprintln!("Error: fade speed must be a non-negative number");
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--goal" => {
+                match parse_value::<String>(&args, &mut i, "goal").map(|g| (GoalMode::from_name(&g), g)) {
+                    Some((Some(goal), _)) => parsed_args.goal = goal,
+                    Some((None, g)) => {
+                        eprintln!("Error: Unknown goal mode `{}` (expected random, near or gray)", g);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "-a" | "--animation" => {
+                match parse_value::<String>(&args, &mut i, "animation").map(|a| (AnimationKind::from_name(&a), a)) {
+                    Some((Some(animation), _)) => parsed_args.animation = animation,
+                    Some((None, a)) => {
+                        eprintln!("Error: Unknown animation `{}` (expected one of {})", a, AnimationKind::NAMES);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--seed" => {
+                match parse_value::<u64>(&args, &mut i, "seed") {
+                    Some(seed) => parsed_args.seed = Some(seed),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--stats" => {
+                parsed_args.stats = true;
+            },
+            "--scalar" => {
+                parsed_args.scalar = true;
+            },
+            "--threads" => {
+                match parse_value::<usize>(&args, &mut i, "threads") {
+                    Some(threads) => parsed_args.threads = threads,
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--bench" => {
+                parsed_args.bench = true;
+            },
+            "--frames" => {
+                match parse_value::<u32>(&args, &mut i, "frames") {
+                    Some(frames) => parsed_args.frames = frames,
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--record" => {
+                match parse_value::<String>(&args, &mut i, "record file") {
+                    Some(path) => parsed_args.record = Some(path),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--replay" => {
+                match parse_value::<String>(&args, &mut i, "replay file") {
+                    Some(path) => parsed_args.replay = Some(path),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--file" => {
+                match parse_value::<String>(&args, &mut i, "file") {
+                    Some(path) => parsed_args.files.push(path),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "-" => {
+                parsed_args.files.push(args[i].clone());
+            },
+            "-u" | "--unique" => {
+                parsed_args.unique = true;
+            },
+            "--grid" => {
+                parsed_args.grid = true;
+            },
+            "--columns" => {
+                match parse_value::<usize>(&args, &mut i, "columns") {
+                    Some(columns) => parsed_args.columns = columns,
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--cell-height" => {
+                match parse_value::<u8>(&args, &mut i, "cell height") {
+                    Some(height) => parsed_args.cell_height = height,
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--label" => {
+                match parse_value::<String>(&args, &mut i, "label").map(|l| (LabelKind::from_name(&l), l)) {
+                    Some((Some(label), _)) => parsed_args.label = label,
+                    Some((None, l)) => {
+                        eprintln!("Error: Unknown label `{}` (expected hex, index, name or none)", l);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--chart" => {
+                parsed_args.chart = true;
+            },
+            "--axes" => {
+                match parse_value::<String>(&args, &mut i, "axes").map(|a| (AxisOrder::from_name(&a), a)) {
+                    Some((Some(axes), _)) => parsed_args.axes = axes,
+                    Some((None, a)) => {
+                        eprintln!("Error: Invalid axes `{}` (expected an order of r, g and b, e.g. rgb or bgr)", a);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--harmony" => {
+                let parsed = parse_value::<String>(&args, &mut i, "harmony").map(|h| {
+                    let harmonies = if h == "all" { Some(Harmony::ALL.to_vec()) } else { Harmony::from_name(&h).map(|h| vec![h]) };
+                    (harmonies, h)
+                });
+                match parsed {
+                    Some((Some(harmonies), _)) => parsed_args.harmonies.extend(harmonies),
+                    Some((None, h)) => {
+                        eprintln!(
+                            "Error: Unknown harmony `{}` (expected complementary, split, analogous, triadic, tetradic, tints, shades, tones or all)",
+                            h
+                        );
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--export" => {
+                parsed_args.export = true;
+            },
+            "--theme" => {
+                parsed_args.theme = true;
+            },
+            "--simulate" => {
+                match parse_value::<String>(&args, &mut i, "simulate").map(|d| (Deficiency::from_name(&d), d)) {
+                    Some((Some(deficiency), _)) => parsed_args.simulate = Some(deficiency),
+                    Some((None, d)) => {
+                        eprintln!(
+                            "Error: Unknown deficiency `{}` (expected protanopia, deuteranopia, tritanopia or achromatopsia)",
+                            d
+                        );
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--severity" => {
+                match parse_value::<f32>(&args, &mut i, "severity") {
+                    Some(severity) if (0.0..=1.0).contains(&severity) => parsed_args.severity = severity,
+                    Some(severity) => {
+                        eprintln!("Error: Invalid severity value `{}` (from 0 to 1)", severity);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--compare" => {
+                parsed_args.compare = true;
+            },
+            "--analyze" => {
+                parsed_args.analyze = true;
+            },
+            "--min-distance" => {
+                match parse_value::<f32>(&args, &mut i, "min distance") {
+                    Some(distance) if distance.is_finite() && distance >= 0.0 => parsed_args.min_distance = distance,
+                    Some(distance) => {
+                        eprintln!("Error: Invalid min distance value `{}` (a finite ΔE of 0 or more)", distance);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--extract" => {
+                match parse_value::<String>(&args, &mut i, "image") {
+                    Some(path) => parsed_args.extract = Some(path),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--colors" => {
+                match parse_value::<usize>(&args, &mut i, "colors") {
+                    Some(colors) if colors >= 1 => parsed_args.colors = colors,
+                    Some(colors) => {
+                        eprintln!("Error: Invalid colors value `{}` (at least 1)", colors);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--view" => {
+                match parse_value::<String>(&args, &mut i, "image") {
+                    Some(path) => parsed_args.view = Some(path),
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--depth" => {
+                match parse_value::<String>(&args, &mut i, "depth").map(|s| (ColorDepth::from_name(&s), s)) {
+                    Some((Some(depth), _)) => parsed_args.depth = depth,
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid depth `{}` (expected truecolor, 256 or 16)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--dither" => {
+                match parse_value::<String>(&args, &mut i, "dither").map(|s| (Dither::from_name(&s), s)) {
+                    Some((Some(dither), _)) => parsed_args.dither = dither,
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid dither `{}` (expected none, ordered or floyd)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--graphics" => {
+                match parse_value::<String>(&args, &mut i, "graphics").map(|s| (GraphicsMode::from_name(&s), s)) {
+                    Some((Some(mode), _)) => parsed_args.graphics = mode,
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid graphics mode `{}` (expected auto, sixel, kitty or blocks)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--easing" => {
+                match parse_value::<String>(&args, &mut i, "easing").map(|s| (Easing::from_name(&s), s)) {
+                    Some((Some(easing), _)) => parsed_args.easing = easing,
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid easing `{}` (expected linear, ease, ease-in, ease-out, ease-in-out, smoothstep, cubic-bezier(x1, y1, x2, y2) or steps(n))", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--hint" => {
+                let parsed = parse_value::<String>(&args, &mut i, "hint").map(|s| {
+                    let value = s.strip_suffix('%').unwrap_or(&s).parse::<f32>().ok().map(|v| v / 100.0);
+                    (value.filter(|v| (0.0..=1.0).contains(v)), s)
+                });
+                match parsed {
+                    Some((Some(hint), _)) => parsed_args.hint = Some(hint),
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid hint `{}` (expected a percentage from 0% to 100%)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--ramp" => {
+                parsed_args.ramp = true;
+            },
+            "--steps" => {
+                match parse_value::<usize>(&args, &mut i, "steps") {
+                    Some(steps) if steps >= 2 => parsed_args.steps = steps,
+                    Some(steps) => {
+                        eprintln!("Error: Invalid steps value `{}` (at least 2)", steps);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--name" => {
+                match parse_value::<String>(&args, &mut i, "name") {
+                    Some(name) => parsed_args.name = name,
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--contrast" => {
+                match parse_value::<f32>(&args, &mut i, "contrast") {
+                    Some(contrast) if (1.0..=21.0).contains(&contrast) => parsed_args.contrast = contrast,
+                    Some(contrast) => {
+                        eprintln!("Error: Invalid contrast value `{}` (a ratio from 1 to 21)", contrast);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--format" => {
+                match parse_value::<String>(&args, &mut i, "format").map(|f| (ExportFormat::from_name(&f), f)) {
+                    Some((Some(format), _)) => parsed_args.format = Some(format),
+                    Some((None, f)) => {
+                        eprintln!("Error: Unknown format `{}` (expected kitty, alacritty, xresources, css, json, hex or rgb)", f);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--highlight" => {
+                parsed_args.highlight = true;
+            },
+            "--text" => {
+                parsed_args.text = true;
+            },
+            "--palette" => {
+                match parse_value::<String>(&args, &mut i, "palette").map(|p| (Gradient::named(&p), p)) {
+                    Some((Some(palette), _)) => parsed_args.palette = Some(palette),
+                    Some((None, p)) => {
+                        eprintln!("Error: Unknown palette `{}` (expected {})", p, Gradient::NAMES.join(", "));
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--direction" => {
+                match parse_value::<String>(&args, &mut i, "direction").map(|d| (Direction::from_name(&d), d)) {
+                    Some((Some(direction), _)) => parsed_args.direction = direction,
+                    Some((None, d)) => {
+                        eprintln!("Error: Unknown direction `{}` (expected horizontal, vertical or diagonal)", d);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--animate" => {
+                parsed_args.animate = true;
+            },
+            "--duration" => {
+                match parse_value::<f32>(&args, &mut i, "duration") {
+                    Some(duration) if duration.is_finite() && duration >= 0.0 => parsed_args.duration = duration,
+                    Some(duration) => {
+                        eprintln!("Error: Invalid duration value `{}` (0 or more seconds)", duration);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
+            "--size" => {
+                match parse_value::<String>(&args, &mut i, "size").map(|s| (parse_size(&s), s)) {
+                    Some((Some(size), _)) => parsed_args.size = Some(size),
+                    Some((None, s)) => {
+                        eprintln!("Error: Invalid size `{}` (expected WIDTHxHEIGHT, e.g. 200x60)", s);
+                        parsed_args.error = true;
+                        return parsed_args;
+                    },
+                    None => {
+                        parsed_args.error = true;
+                        return parsed_args;
+                    }
+                }
+            },
             "-h" | "--help" => {
                 parsed_args.help = true;
             },
@@ -93,45 +792,94 @@ pub fn parse_args() -> Args {
         i += 1;
     }
 
-    return parsed_args;
+    parsed_args
+}
+
+
+// parse the value following the option at `i`, advancing past it
+fn parse_value<T: FromStr>(args: &[String], i: &mut usize, name: &str) -> Option<T> {
+    if *i + 1 >= args.len() {
+        eprintln!("Error: Missing value for {}", name);
+        return None;
+    }
+    match args[*i + 1].parse::<T>() {
+        Ok(v) => {
+            *i += 1;
+            Some(v)
+        },
+        Err(_) => {
+            eprintln!("Error: Invalid {} value `{}`", name, args[*i + 1]);
+            None
+        }
+    }
+}
+
+
+// `WIDTHxHEIGHT`, both non-zero
+fn parse_size(s: &str) -> Option<(u16, u16)> {
+    let (w, h) = s.split_once(['x', 'X'])?;
+    match (w.parse::<u16>(), h.parse::<u16>()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+
+// replace every `random` value with a random hex color, returns whether there were any
+pub fn resolve_random(values: &mut [String], rng: &mut SimpleRng) -> bool {
+    let mut any = false;
+    for value in values.iter_mut().filter(|v| v.eq_ignore_ascii_case("random")) {
+        let (r, g, b): (u8, u8, u8) = (rng.random(), rng.random(), rng.random());
+        *value = format!("#{:02x}{:02x}{:02x}", r, g, b);
+        any = true;
+    }
+    any
 }
 
 
 pub fn single(values: &[String], width: u8, numbered: bool) {
     let input = &values[0];
-    if let Some(code) = named_color_to_ansi(input) {
-        print_block_ansi(code, width, numbered);
-    } else if let Ok(code) = input.parse::<u8>() {
-        print_block_ansi(code, width, numbered);
-    } else if is_valid_hex_color(input) {
-        let hex = input.strip_prefix('#').unwrap_or(input);
-        let pairs: Vec<&str> = hex.as_bytes().chunks(2)
-            .map(|c| str::from_utf8(c).unwrap())
-            .collect();
-        print_block_hex(pairs, width);
-    } else {
-        eprintln!("Error: Input color `{}` not recognized (see --help)", input);
+    match parse_color(input) {
+        Some(ColorValue::Ansi(code)) => {
+            warn_if_approximated(input, code);
+            print_block_ansi(code, width, numbered);
+        },
+        Some(color) => print_block_hex(color.rgb(), width),
+        None => eprintln!("Error: Input color `{}` not recognized (see --help)", input),
     }
 }
 
 
-pub fn many(values: &[String], width: u8, inline: bool, numbered: bool, fit_width: bool) {
+// two values are a range of palette entries when both are plain ANSI codes, and a
+// gradient between the two colors in any other notation
+#[derive(Debug, PartialEq)]
+enum Pair {
+    Range(u8, u8),
+    Gradient((u8, u8, u8), (u8, u8, u8)),
+}
+
+fn pair(a: &str, b: &str) -> Option<Pair> {
+    if let (Ok(c1), Ok(c2)) = (a.parse::<u8>(), b.parse::<u8>()) {
+        return Some(Pair::Range(c1, c2));
+    }
+    Some(Pair::Gradient(parse_color(a)?.rgb(), parse_color(b)?.rgb()))
+}
+
+pub fn many(values: &[String], width: u8, inline: bool, numbered: bool, shape: &GradientShape, strip: &StripOptions) {
     let a = &values[0];
     let b = &values[1];
-    if let (Ok(c1), Ok(c2)) = (a.parse::<u8>(), b.parse::<u8>()) {
-        print_blocks_ansi(c1, c2, width, inline, numbered);
-    } else if is_valid_hex_color(a) && is_valid_hex_color(b) {
-        let h1 = a.strip_prefix('#').unwrap_or(a);
-        let h2 = b.strip_prefix('#').unwrap_or(b);
-        let p1: Vec<&str> = h1.as_bytes().chunks(2)
-            .map(|c| str::from_utf8(c).unwrap())
-            .collect();
-        let p2: Vec<&str> = h2.as_bytes().chunks(2)
-            .map(|c| str::from_utf8(c).unwrap())
-            .collect();
-        print_hex_gradient(p1, p2, fit_width);
-    } else {
-        eprintln!("Error: Invalid color/range: `{}` and `{}`", a, b);
+    match pair(a, b) {
+        Some(Pair::Range(c1, c2)) if strip.sample.is_none() => print_blocks_ansi(c1, c2, width, inline, numbered),
+        // --sample reads a range as a gradient between the colors at its ends
+        Some(Pair::Range(c1, c2)) => {
+            let gradient = shape.apply(Gradient::new(vec![ansi_to_rgb(c1), ansi_to_rgb(c2)]));
+            print_hex_gradient(&gradient, strip);
+        },
+        Some(Pair::Gradient(c1, c2)) => {
+            let gradient = shape.apply(Gradient::new(vec![c1, c2]));
+            print_hex_gradient(&gradient, strip);
+        },
+        None => eprintln!("Error: Invalid color/range: `{}` and `{}`", a, b),
     }
 }
 
@@ -142,17 +890,137 @@ pub fn print_help() {
     println!("\u{001b}[4mUsage:\u{001b}[24m clrblk [OPTIONS] [VALUES]...");
     println!();
     println!("\u{001b}[4mArguments:\u{001b}[24m");
-    println!("  [VALUES]...  Color(s) to display: ANSI codes, names, or hex strings (#RRGGBB)");
+    println!("  [VALUES]...  Color(s) to display: ANSI codes, names, hex (#RGB or #RRGGBB), rgb(), hsl() or `random`");
+    println!("               `-` reads colors from stdin instead; more than two colors are shown as a grid");
+    println!("               A CSS linear-gradient() or radial-gradient() expression is drawn as a gradient");
     println!();
     println!("\u{001b}[4mOptions:\u{001b}[24m");
     println!("  -w, --width <WIDTH>  Width of blocks [default: 6 character spaces]");
     println!("  -i, --inline         Multiple colors shown in one line (only for ANSI ranges)");
-    println!("  -n, --numbered       Print color number(s) before each block (only for ANSI)");
-    println!("  -f, --fit           Fit hex gradient to full terminal width");
-    println!("  -r, --rainbow        Print a full   6-phase RGB rainbow");
-    println!("  -g, --grayscale      Print a grayscale gradient");
+    println!("  -n, --numbered       Print color number(s) before each block (only for ANSI, inside cells for --chart)");
+    println!();
+    println!("  -f, --fit            Fit gradients, --rainbow and --grayscale to the full terminal width");
+    println!("      --fill           Draw a CSS gradient over the whole terminal in 2D instead of as a strip");
+    println!("      --easing <FN>    CSS easing between gradient stops: linear, ease, ease-in, ease-out, ease-in-out,");
+    println!("                       smoothstep, cubic-bezier(x1, y1, x2, y2) or steps(n[, jump-start|jump-end|...])");
+    println!("      --hint <PERCENT> Where between two gradient stops their halfway color falls [default: 50%]");
+    println!("      --sample <N>     Print N evenly spaced colors of a gradient instead of drawing it, as hex, rgb");
+    println!("                       or json lines (see --format) [default format: hex]");
+    println!("      --swatches       Draw a --width block before each --sample color");
+    println!();
+    println!("      --depth <DEPTH>  Colors --view and gradients draw with: truecolor, 256 or 16 [default: truecolor]");
+    println!("      --dither <KIND>  Dithering below truecolor: none, ordered or floyd [default: none]. Gradients");
+    println!("                       mix two palette colors per cell with shade characters");
+    println!("      --graphics <MODE>");
+    println!("                       Draw blocks, gradients and unlabeled grids as sixel or kitty images, or auto to");
+    println!("                       ask the terminal, falling back to blocks [default: blocks]");
+    println!();
+    println!("  -r, --rainbow        Print a rainbow, one cell per step of the 6 RGB phases up to the terminal width");
+    println!("      --hue-path <PATH>");
+    println!("                       Hues --rainbow goes through: hsv, hsl or oklch (even lightness and chroma) [default: hsv]");
+    println!("      --saturation <0-1>");
+    println!("                       --rainbow saturation; for oklch, of the most chroma every hue can show [default: 1]");
+    println!("      --lightness <0-1>");
+    println!("                       --rainbow value (hsv) or lightness [default: 1 for hsv, 0.5 for hsl, 0.7 for oklch]");
+    println!("      --cycles <N>     Times --rainbow goes around the hue circle [default: 1]");
+    println!("      --reverse        Run --rainbow hues backwards, from red through magenta");
+    println!("  -g, --grayscale      Print a grayscale gradient, up and back down");
+    println!("      --gray-steps <KIND>");
+    println!("                       Spacing of --grayscale steps: gamma (even sRGB values), linear (even light)");
+    println!("                       or perceptual (even OKLab lightness) [default: gamma]");
+    println!();
     println!("      --crazy          Show a fullscreen grid of cells of random colors that each fade to new random colors");
+    println!("                       (press ? while it runs for keyboard controls)");
+    println!("      --fps <FPS>      Target frame rate for --crazy [default: 50]");
+    println!("      --fade-speed <N> Color steps per second each cell fades by [default: 50]");
+    println!("      --goal <MODE>    How cells pick new colors: random, near, gray [default: random]");
+    println!("  -a, --animation <NAME>");
+    println!("                       Animation for --crazy: fade, plasma, fire, wave, life, palette [default: fade]");
+    println!("      --seed <SEED>    Seed for --crazy and `random` colors, printed on exit to replay a run");
+    println!("      --stats          Show actual fps and render time while animating");
+    println!("      --scalar         Use the scalar fade instead of SIMD");
+    println!("      --threads <N>    Threads to split animation rows across, 0 for auto [default: 0]");
+    println!("      --record <FILE>  Record the animation to an asciicast v2 file");
+    println!("      --bench          Time the animation off-screen instead of showing it");
+    println!("      --frames <N>     Frames to run with --bench [default: 300]");
+    println!("      --size <WxH>     Grid size for --bench [default: terminal size]");
+    println!("      --replay <FILE>  Play back an asciicast v2 recording at its original timing");
+    println!();
+    println!("      --text           Color text from stdin or --file along a gradient of the given colors");
+    println!("      --palette <NAME> Gradient for --text without colors, or to draw or --sample on its own:");
+    println!("                       {} [default: rainbow]", Gradient::NAMES.join(", "));
+    println!("      --direction <DIR>");
+    println!("                       Gradient direction for --text: horizontal, vertical, diagonal [default: horizontal]");
+    println!("      --animate        Sweep the gradient across each line of --text before it settles");
+    println!("      --duration <SECS>");
+    println!("                       Seconds each line animates for with --animate [default: 0.5]");
+    println!();
+    println!("      --harmony <KIND> Color scheme from one color: complementary, split, analogous, triadic,");
+    println!("                       tetradic, tints, shades, tones or all (repeatable)");
+    println!("      --theme          Generate 16 terminal colors for a background color and optional accent");
+    println!("      --contrast <RATIO>");
+    println!("                       Minimum contrast of --theme colors against the background [default: 4.5]");
+    println!("      --ramp           Build a light to dark scale (50 to 950) around one color");
+    println!("      --steps <N>      Steps in a --ramp [default: 11]");
+    println!("      --name <NAME>    Name for exported --ramp or --extract variables [default: color]");
+    println!("      --format <FMT>   Export format: kitty, alacritty or xresources for --theme [default: kitty],");
+    println!("                       css or json for --ramp [default: css] and --extract [default: hex list],");
+    println!("                       hex, rgb or json for --sample [default: hex]");
+    println!("      --export         Print --harmony colors as a plain hex list, --theme as a config, --ramp as CSS or JSON");
+    println!("                       or the --extract palette");
+    println!();
+    println!("      --extract <IMAGE>");
+    println!("                       Find the dominant colors of a PNG, BMP or PPM image");
+    println!("      --colors <N>     Colors --extract looks for [default: 8]");
+    println!("      --view <IMAGE>   Draw a PNG, BMP or PPM image with half blocks, fit to the terminal");
+    println!();
+    println!("      --highlight      Reprint stdin or --file with each color literal drawn in its color,");
+    println!("                       then list every distinct color");
+    println!("      --file <FILE>    Show every color found in a file (repeatable)");
+    println!("  -u, --unique         Show each color from stdin or --file only once");
+    println!();
+    println!("      --chart          Print the 256 color palette: system colors, color cube and grayscale ramp");
+    println!("      --axes <ORDER>   Cube axes for --chart as faces, rows, columns, e.g. bgr [default: rgb]");
+    println!();
+    println!("      --grid           Show one or two colors as a grid instead of a block or range");
+    println!("      --columns <N>    Grid columns, 0 to fit the terminal [default: 0]");
+    println!("      --cell-height <N>");
+    println!("                       Grid cell height in lines [default: 1]");
+    println!("      --label <KIND>   Text inside grid cells: hex, index, name, none [default: none]");
+    println!();
+    println!("      --analyze        Report how far apart every pair of colors (or --file theme colors) is,");
+    println!("                       and which are too close to tell apart, including with color blindness");
+    println!("      --min-distance <ΔE>");
+    println!("                       OKLab distance (x100) below which --analyze flags a pair [default: 8]");
+    println!();
+    println!("      --simulate <KIND>");
+    println!("                       Show every color as seen with protanopia, deuteranopia, tritanopia or achromatopsia");
+    println!("      --severity <0-1> Strength of --simulate [default: 1]");
+    println!("      --compare        Draw the output twice, without and with --simulate");
+    println!();
     println!("  -h, --help           Print help information");
     println!("  -V, --version        Print version information");
-    return;
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_values_in_any_notation() {
+        assert_eq!(parse_color("208"), Some(ColorValue::Ansi(208)));
+        assert_eq!(parse_color("#f80"), Some(ColorValue::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("rgb(255, 136, 0)"), Some(ColorValue::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("#ff8"), parse_color("#ffff88"));
+    }
+
+    #[test]
+    fn pairs_are_ranges_or_gradients() {
+        assert_eq!(pair("16", "21"), Some(Pair::Range(16, 21)));
+        assert_eq!(pair("#f00", "rgb(0 0 255)"), Some(Pair::Gradient((255, 0, 0), (0, 0, 255))));
+        assert_eq!(pair("#0f0", "#00f"), Some(Pair::Gradient((0, 255, 0), (0, 0, 255))));
+        assert_eq!(pair("red", "blue"), Some(Pair::Gradient(ansi_to_rgb(1), ansi_to_rgb(4))));
+        assert_eq!(pair("16", "nope"), None);
+    }
+}
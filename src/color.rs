@@ -0,0 +1,252 @@
+// color values and conversions between color models
+
+use crate::ansi::{ansi_to_rgb, named_color_to_ansi};
+use crate::validate::is_valid_hex_color;
+
+// `h` in degrees, `s` and `v` in 0..=1
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    (to_u8(r + m), to_u8(g + m), to_u8(b + m))
+}
+
+// 0..=1 to 0..=255, clamping anything outside
+pub fn to_u8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+pub fn to_hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// a color as given on the command line: a palette index or an exact RGB value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorValue {
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl ColorValue {
+    // palette indices resolve through xterm's default colors
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Self::Ansi(code) => ansi_to_rgb(code),
+            Self::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+// sRGB channel to linear light, both 0..=1
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+// WCAG relative luminance, 0 for black to 1 for white
+pub fn relative_luminance(r: u8, g: u8, b: u8) -> f32 {
+    let linear = |c: u8| srgb_to_linear(c as f32 / 255.0);
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+// WCAG contrast ratio, from 1 (same luminance) to 21 (black on white)
+pub fn contrast_ratio(a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+    let (la, lb) = (relative_luminance(a.0, a.1, a.2), relative_luminance(b.0, b.1, b.2));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+// ΔE in OKLab: euclidean distance, about 0.02 for a just noticeable difference
+pub fn oklab_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+    let (l1, a1, b1) = Oklch::from_rgb(a.0, a.1, a.2).to_lab();
+    let (l2, a2, b2) = Oklch::from_rgb(b.0, b.1, b.2).to_lab();
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+// OKLab in polar form: lightness 0..=1, chroma from 0 (gray) to ~0.37, hue in degrees
+#[derive(Clone, Copy, Debug)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl Oklch {
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        // the matrices carry more digits than f32 holds, so work in f64
+        let (r, g, b) = (
+            srgb_to_linear(r as f32 / 255.0) as f64,
+            srgb_to_linear(g as f32 / 255.0) as f64,
+            srgb_to_linear(b as f32 / 255.0) as f64,
+        );
+        let l = (0.41222147 * r + 0.53633254 * g + 0.05144599 * b).cbrt();
+        let m = (0.21190350 * r + 0.68069955 * g + 0.10739696 * b).cbrt();
+        let s = (0.08830246 * r + 0.28171884 * g + 0.62997870 * b).cbrt();
+
+        let lightness = 0.21045426 * l + 0.79361778 * m - 0.00407205 * s;
+        let a = 1.97799850 * l - 2.42859221 * m + 0.45059371 * s;
+        let b = 0.02590404 * l + 0.78277177 * m - 0.80867577 * s;
+        Self { l: lightness as f32, c: a.hypot(b) as f32, h: b.atan2(a).to_degrees().rem_euclid(360.0) as f32 }
+    }
+
+    // back to OKLab's cartesian L, a, b
+    pub fn to_lab(self) -> (f32, f32, f32) {
+        let h = self.h.to_radians();
+        (self.l, self.c * h.cos(), self.c * h.sin())
+    }
+
+    pub fn from_lab(l: f32, a: f32, b: f32) -> Self {
+        Self { l, c: a.hypot(b), h: b.atan2(a).to_degrees().rem_euclid(360.0) }
+    }
+
+    // linear RGB, possibly outside 0..=1 when the color is out of gamut
+    fn to_linear(self) -> (f32, f32, f32) {
+        let (lightness, c, h) = (self.l as f64, self.c as f64, (self.h as f64).to_radians());
+        let (a, b) = (c * h.cos(), c * h.sin());
+        let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+        let m = (lightness - 0.10556135 * a - 0.06385417 * b).powi(3);
+        let s = (lightness - 0.08948418 * a - 1.29148555 * b).powi(3);
+        (
+            (4.07674166 * l - 3.30771159 * m + 0.23096993 * s) as f32,
+            (-1.26843800 * l + 2.60975740 * m - 0.34131940 * s) as f32,
+            (-0.00419609 * l - 0.70341861 * m + 1.70761470 * s) as f32,
+        )
+    }
+
+    fn in_gamut(self) -> bool {
+        let (r, g, b) = self.to_linear();
+        [r, g, b].iter().all(|v| (-1e-4..=1.0 + 1e-4).contains(v))
+    }
+
+    // the most chroma a color of this lightness and hue can have and still be displayed
+    pub fn max_chroma(l: f32, h: f32) -> f32 {
+        Self { l, c: 0.5, h }.gamut_chroma()
+    }
+
+    // chroma reduced until the color is displayable
+    fn gamut_chroma(self) -> f32 {
+        if self.in_gamut() {
+            return self.c;
+        }
+        let (mut lo, mut hi) = (0.0, self.c);
+        for _ in 0..24 {
+            let mid = (lo + hi) / 2.0;
+            if (Self { c: mid, ..self }).in_gamut() { lo = mid } else { hi = mid }
+        }
+        lo
+    }
+
+    // nearest displayable color, keeping lightness and hue and giving up chroma
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        let mut color = Self { l: self.l.clamp(0.0, 1.0), ..self };
+        color.c = color.gamut_chroma();
+        let (r, g, b) = color.to_linear();
+        (to_u8(linear_to_srgb(r.max(0.0))), to_u8(linear_to_srgb(g.max(0.0))), to_u8(linear_to_srgb(b.max(0.0))))
+    }
+}
+
+// `h` in degrees, `s` and `l` in 0..=1
+pub fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let v = l + s * l.min(1.0 - l);
+    let sv = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+    hsv_to_rgb(h, sv, v)
+}
+
+// any notation accepted for CLI values: ANSI names, ANSI codes, hex (`#rgb` or
+// `#rrggbb`) or CSS style `rgb()` and `hsl()`
+pub fn parse_color(input: &str) -> Option<ColorValue> {
+    if let Some(code) = named_color_to_ansi(input) {
+        Some(ColorValue::Ansi(code))
+    } else if let Ok(code) = input.parse::<u8>() {
+        Some(ColorValue::Ansi(code))
+    } else if is_valid_hex_color(input) {
+        let hex = input.strip_prefix('#').unwrap_or(input);
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+        Some(ColorValue::Rgb(channel(0), channel(2), channel(4)))
+    } else if let Some(hex) = input.strip_prefix('#').filter(|h| h.len() == 3 && h.chars().all(|c| c.is_ascii_hexdigit())) {
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or(0) * 17;
+        Some(ColorValue::Rgb(channel(0), channel(1), channel(2)))
+    } else {
+        parse_css_function(input)
+    }
+}
+
+// `rgb(255, 128, 0)`, `rgb(100% 50% 0% / 0.5)`, `hsl(30deg, 100%, 50%)` and the
+// `rgba`/`hsla` spellings; alpha is accepted but ignored
+fn parse_css_function(input: &str) -> Option<ColorValue> {
+    let (name, rest) = input.split_once('(')?;
+    let args: Vec<&str> = rest
+        .strip_suffix(')')?
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .collect();
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+
+    // a number, or a percentage of `full`
+    let value = |arg: &str, full: f32| -> Option<f32> {
+        match arg.strip_suffix('%') {
+            Some(pct) => pct.parse::<f32>().ok().map(|p| p / 100.0 * full),
+            None => arg.parse::<f32>().ok(),
+        }
+    };
+    let fraction = |arg: &str| -> Option<f32> { Some(arg.strip_suffix('%')?.parse::<f32>().ok()? / 100.0) };
+
+    match name.trim().to_lowercase().as_str() {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| value(arg, 255.0).map(|v| v.clamp(0.0, 255.0).round() as u8);
+            Some(ColorValue::Rgb(channel(args[0])?, channel(args[1])?, channel(args[2])?))
+        },
+        "hsl" | "hsla" => {
+            let hue = args[0].strip_suffix("deg").unwrap_or(args[0]).parse::<f32>().ok()?;
+            let (r, g, b) = hsl_to_rgb(hue, fraction(args[1])?.clamp(0.0, 1.0), fraction(args[2])?.clamp(0.0, 1.0));
+            Some(ColorValue::Rgb(r, g, b))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_notation() {
+        assert_eq!(parse_color("bright_red"), Some(ColorValue::Ansi(9)));
+        assert_eq!(parse_color("208"), Some(ColorValue::Ansi(208)));
+        assert_eq!(parse_color("#ff8000"), Some(ColorValue::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("#f80"), Some(ColorValue::Rgb(255, 136, 0)));
+        assert_eq!(parse_color("rgb(255, 128, 0)"), Some(ColorValue::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("RGBA(100% 50% 0% / .5)"), Some(ColorValue::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("hsl(120deg, 100%, 25%)"), Some(ColorValue::Rgb(0, 128, 0)));
+        assert_eq!(parse_color("hsl(0, 0%, 100%)"), Some(ColorValue::Rgb(255, 255, 255)));
+        assert_eq!(parse_color("rgb(1, 2)"), None);
+        assert_eq!(parse_color("f80"), None);
+    }
+
+    #[test]
+    fn oklch_round_trips_and_maps_into_gamut() {
+        for rgb in [(255, 136, 0), (0, 0, 0), (255, 255, 255), (12, 200, 99), (90, 40, 220)] {
+            assert_eq!(Oklch::from_rgb(rgb.0, rgb.1, rgb.2).to_rgb(), rgb);
+        }
+        let white = Oklch::from_rgb(255, 255, 255);
+        assert!((white.l - 1.0).abs() < 1e-3 && white.c < 1e-3);
+        // far more chroma than sRGB holds still lands on a real color of that hue
+        let (r, g, b) = Oklch { l: 0.6, c: 0.5, h: 30.0 }.to_rgb();
+        assert!(r > g && r > b);
+    }
+}
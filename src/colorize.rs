@@ -0,0 +1,262 @@
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    ansi::{push_color, ColorDepth},
+    cast::CastWriter,
+    gradient::Gradient,
+    terminal::terminal_size,
+};
+
+//////////////////////////////////////////////////////////////////////////////////////////
+/// Gradient colored text (lolcat style) for --text
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "horizontal" | "h" => Some(Self::Horizontal),
+            "vertical" | "v"   => Some(Self::Vertical),
+            "diagonal" | "d"   => Some(Self::Diagonal),
+            _ => None,
+        }
+    }
+}
+
+pub struct TextOptions {
+    pub gradient: Gradient,
+    pub direction: Direction,
+    pub animate: bool,
+    pub duration: f32, // seconds each line animates for
+    pub fps: u32,
+    pub record: Option<String>, // asciicast file to record the animation to
+}
+
+// a line split into what gets drawn and what passes through untouched
+#[derive(Debug, PartialEq)]
+enum Piece<'a> {
+    Char(char, usize), // with its display width
+    Escape(Cow<'a, str>),
+}
+
+// columns a character takes up: 0 for combining marks and controls, 2 for wide
+// (East Asian and emoji) characters, 1 otherwise
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x00..=0x1f | 0x7f..=0x9f => 0,
+        0x0300..=0x036f | 0x0483..=0x0489 | 0x0591..=0x05bd | 0x0610..=0x061a
+        | 0x064b..=0x065f | 0x200b..=0x200f | 0x20d0..=0x20ff | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f | 0xe0100..=0xe01ef => 0,
+        0x1100..=0x115f | 0x231a..=0x231b | 0x2329..=0x232a | 0x23e9..=0x23ec
+        | 0x25fd..=0x25fe | 0x2614..=0x2615 | 0x2648..=0x2653 | 0x26aa..=0x26ab
+        | 0x26bd..=0x26be | 0x26f5 | 0x26fa | 0x26fd | 0x2705 | 0x270a..=0x270b
+        | 0x2728 | 0x274c | 0x2753..=0x2755 | 0x2795..=0x2797 | 0x2b1b..=0x2b1c
+        | 0x2e80..=0x303e | 0x3041..=0x33ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf | 0xac00..=0xd7a3 | 0xf900..=0xfaff | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60 | 0xffe0..=0xffe6 | 0x1f300..=0x1f64f | 0x1f680..=0x1f6ff
+        | 0x1f900..=0x1f9ff | 0x1fa70..=0x1faff | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+// length of the escape sequence at the start of `s` (which begins with ESC)
+fn escape_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    match bytes.get(1) {
+        // CSI: parameters and intermediates up to a final byte in @..~
+        Some(b'[') => bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)).map_or(bytes.len(), |i| i + 3),
+        // OSC: up to BEL or ST (ESC \)
+        Some(b']') => {
+            let mut i = 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    0x07 => return i + 1,
+                    0x1b if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+                    _ => i += 1,
+                }
+            }
+            bytes.len()
+        },
+        Some(_) => 1 + s[1..].chars().next().map_or(0, char::len_utf8),
+        None => 1,
+    }
+}
+
+// an SGR sequence (`ESC [ ... m`) without its foreground and background colors, so
+// bold, underline and the like survive while the gradient replaces the colors
+fn without_colors(seq: &str) -> Option<String> {
+    let params = &seq[2..seq.len() - 1];
+    let mut kept = Vec::new();
+    let mut iter = params.split(';');
+    while let Some(param) = iter.next() {
+        let code = param.split(':').next().and_then(|c| c.parse::<u32>().ok()).unwrap_or(0);
+        match code {
+            // extended colors take their arguments as the following parameters
+            // unless they come colon separated
+            38 | 48 if !param.contains(':') => {
+                let args = match iter.next() {
+                    Some("5") => 1,
+                    Some("2") => 3,
+                    _ => 0,
+                };
+                iter.by_ref().take(args).for_each(drop);
+            },
+            30..=39 | 40..=49 | 90..=97 | 100..=107 => {},
+            _ => kept.push(if param.is_empty() { "0" } else { param }),
+        }
+    }
+    (!kept.is_empty()).then(|| format!("\x1b[{}m", kept.join(";")))
+}
+
+// the input's own colors are dropped since the gradient replaces them; other
+// attributes and sequences like OSC 8 links are kept
+fn split_line(line: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    let mut col = 0;
+    while let Some(c) = rest.chars().next() {
+        if c == '\x1b' {
+            let len = escape_len(rest);
+            let seq = &rest[..len];
+            if seq.starts_with("\x1b[") && seq.ends_with('m') {
+                if let Some(attributes) = without_colors(seq) {
+                    pieces.push(Piece::Escape(Cow::Owned(attributes)));
+                }
+            } else {
+                pieces.push(Piece::Escape(Cow::Borrowed(seq)));
+            }
+            rest = &rest[len..];
+            continue;
+        }
+        // tabs stop at every 8th column
+        let width = if c == '\t' { 8 - col % 8 } else { char_width(c) };
+        col += width;
+        pieces.push(Piece::Char(c, width));
+        rest = &rest[c.len_utf8()..];
+    }
+    pieces
+}
+
+fn line_width(pieces: &[Piece]) -> usize {
+    pieces.iter().map(|p| if let Piece::Char(_, w) = p { *w } else { 0 }).sum()
+}
+
+struct Layout {
+    rows: usize,
+    columns: usize, // widest line
+    direction: Direction,
+    cyclic: bool,
+}
+
+impl Layout {
+    // position along the gradient of a character at `col` on line `row`; rows
+    // count double diagonally since cells are about twice as tall as wide. Cyclic
+    // gradients stop one step short of the end so the first color isn't repeated
+    fn position(&self, row: usize, col: usize) -> f32 {
+        let (pos, last) = match self.direction {
+            Direction::Horizontal => (col, self.columns.saturating_sub(1)),
+            Direction::Vertical => (row, self.rows.saturating_sub(1)),
+            Direction::Diagonal => (col + 2 * row, self.columns.saturating_sub(1) + 2 * self.rows.saturating_sub(1)),
+        };
+        let span = if self.cyclic { last + 1 } else { last };
+        pos as f32 / span.max(1) as f32
+    }
+}
+
+fn render_line(buf: &mut String, pieces: &[Piece], row: usize, layout: &Layout, gradient: &Gradient, phase: f32) {
+    let mut col = 0;
+    for piece in pieces {
+        match *piece {
+            Piece::Escape(ref seq) => buf.push_str(seq),
+            Piece::Char(c, width) => {
+                if !c.is_whitespace() {
+                    let (r, g, b) = gradient.at_wrapped(layout.position(row, col) + phase);
+                    push_color(buf, ColorDepth::TrueColor, true, r, g, b);
+                }
+                buf.push(c);
+                col += width;
+            },
+        }
+    }
+    buf.push_str("\x1b[0m");
+}
+
+// color every line of `text` along the gradient; animated lines cycle through the
+// gradient once before settling on their place in the static layout
+pub fn colorize(text: &str, opts: &TextOptions) -> io::Result<()> {
+    let lines: Vec<Vec<Piece>> = text.lines().map(split_line).collect();
+    let layout = Layout {
+        rows: lines.len(),
+        columns: lines.iter().map(|l| line_width(l)).max().unwrap_or(0),
+        direction: opts.direction,
+        cyclic: opts.gradient.is_cyclic(),
+    };
+
+    let mut out: Box<dyn Write> = match &opts.record {
+        Some(path) => {
+            let (width, height) = terminal_size().unwrap_or((80, 24));
+            Box::new(CastWriter::create(path, io::stdout().lock(), width, height, "clrblk --text")?)
+        },
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut buf = String::new();
+    let frames = if opts.animate { (opts.duration * opts.fps.max(1) as f32) as usize } else { 0 };
+    let frame_time = Duration::from_secs_f32(1.0 / opts.fps.max(1) as f32);
+
+    for (row, pieces) in lines.iter().enumerate() {
+        for frame in (1..=frames).rev() {
+            buf.clear();
+            buf.push('\r');
+            render_line(&mut buf, pieces, row, &layout, &opts.gradient, frame as f32 / frames as f32);
+            out.write_all(buf.as_bytes())?;
+            out.flush()?;
+            thread::sleep(frame_time);
+        }
+
+        buf.clear();
+        if frames > 0 {
+            buf.push('\r');
+        }
+        render_line(&mut buf, pieces, row, &layout, &opts.gradient, 0.0);
+        let _ = writeln!(buf);
+        out.write_all(buf.as_bytes())?;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_color_codes_but_keeps_other_sequences() {
+        let link = "\x1b]8;;https://example.com\x1b\\";
+        let line = format!("\x1b[1;31ma\x1b[0m{}b\x1b[2K\x1b[38;2;1;2;3;4m\x1b[48;5;9mc", link);
+        assert_eq!(
+            split_line(&line),
+            [
+                Piece::Escape("\x1b[1m".into()), Piece::Char('a', 1), Piece::Escape("\x1b[0m".into()),
+                Piece::Escape(link.into()), Piece::Char('b', 1), Piece::Escape("\x1b[2K".into()),
+                Piece::Escape("\x1b[4m".into()), Piece::Char('c', 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn widths_of_wide_combining_and_tab() {
+        let pieces = split_line("日本e\u{301}\tx🌈");
+        assert_eq!(line_width(&pieces), 8 + 1 + 2);
+    }
+}
@@ -0,0 +1,666 @@
+use std::ops::Range;
+
+use crate::{
+    ansi::ColorDepth,
+    color::parse_color,
+    gradient::{ColorSpace, Gradient, HueMethod},
+    graphics::{self, cell_pixels, print_raster, Raster},
+    terminal::{cell_aspect, terminal_size},
+    view::{dither, print_pixels, Dither, DEFAULT_CELL_ASPECT},
+};
+
+// CSS `linear-gradient()` and `radial-gradient()` expressions and their `repeating-`
+// forms: the stops become a `Gradient`, the rest says where in a box each point of it falls
+
+// CSS's basic color keywords; `parse_color` reads other names as the terminal's ANSI colors
+const NAMED: [(&str, (u8, u8, u8)); 20] = [
+    ("black", (0, 0, 0)),
+    ("silver", (192, 192, 192)),
+    ("gray", (128, 128, 128)),
+    ("grey", (128, 128, 128)),
+    ("white", (255, 255, 255)),
+    ("maroon", (128, 0, 0)),
+    ("red", (255, 0, 0)),
+    ("purple", (128, 0, 128)),
+    ("fuchsia", (255, 0, 255)),
+    ("magenta", (255, 0, 255)),
+    ("green", (0, 128, 0)),
+    ("lime", (0, 255, 0)),
+    ("olive", (128, 128, 0)),
+    ("yellow", (255, 255, 0)),
+    ("navy", (0, 0, 128)),
+    ("blue", (0, 0, 255)),
+    ("teal", (0, 128, 128)),
+    ("aqua", (0, 255, 255)),
+    ("cyan", (0, 255, 255)),
+    ("orange", (255, 165, 0)),
+];
+
+// what went wrong and the bytes of the expression it is about
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self { message: message.into(), span }
+    }
+
+    // the message, then the expression with the offending part underlined
+    pub fn print(&self, input: &str) {
+        let pad = input[..self.span.start].chars().count();
+        let width = input[self.span.clone()].chars().count().max(1);
+        eprintln!("Error: {}", self.message);
+        eprintln!("  {}", input);
+        eprintln!("  {}{}", " ".repeat(pad), "^".repeat(width));
+    }
+}
+
+// where a linear gradient heads: degrees clockwise from up, or towards a corner
+// (-1 left or top, 1 right or bottom), which depends on the shape of the box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Angle(f32),
+    Corner(f32, f32),
+}
+
+// how far a radial gradient reaches before its last stop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Extent {
+    ClosestSide,
+    FarthestSide,
+    ClosestCorner,
+    FarthestCorner,
+    Percent(f32, f32), // of the box's width and height
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Linear(Direction),
+    Radial { circle: bool, extent: Extent, center: (f32, f32) }, // center as fractions of the box
+}
+
+#[derive(Clone)]
+pub struct CssGradient {
+    pub shape: Shape,
+    pub gradient: Gradient,
+}
+
+// a whitespace separated part of an argument; parentheses keep `rgb(1 2 3)` in one word
+struct Word<'a> {
+    text: &'a str,
+    span: Range<usize>,
+}
+
+// what each argument describes, before missing positions are filled in
+enum Item {
+    Stop((u8, u8, u8), Option<f32>),
+    Hint(f32),
+}
+
+// whether `value` is meant as a gradient expression rather than a color
+pub fn is_gradient(value: &str) -> bool {
+    value.split_once('(').is_some_and(|(name, _)| name.trim().to_lowercase().ends_with("-gradient"))
+}
+
+impl CssGradient {
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let start = input.len() - input.trim_start().len();
+        let end = input.trim_end().len();
+        let Some(open) = input[start..end].find('(').map(|i| start + i) else {
+            return Err(ParseError::new("Expected `(` after the gradient's name", start..end));
+        };
+        let name_span = start..start + input[start..open].trim_end().len();
+        let name = input[name_span.clone()].to_lowercase();
+        let (repeating, kind) = match name.strip_prefix("repeating-") {
+            Some(kind) => (true, kind),
+            None => (false, name.as_str()),
+        };
+        if !matches!(kind, "linear-gradient" | "radial-gradient") {
+            let message = if kind == "conic-gradient" {
+                "Conic gradients aren't supported".to_string()
+            } else {
+                format!("Unknown gradient `{}` (expected linear-gradient or radial-gradient, optionally repeating-)", &input[name_span.clone()])
+            };
+            return Err(ParseError::new(message, name_span));
+        }
+
+        let close = closing_paren(input, open)?;
+        let rest = end - input[close + 1..end].trim_start().len();
+        if rest < end {
+            return Err(ParseError::new(format!("Unexpected `{}` after the gradient", &input[rest..end]), rest..end));
+        }
+        let args = split_args(input, open + 1..close)?;
+
+        // the first argument sets the shape and color space unless it is already a color stop
+        let first = &args[0];
+        let (shape, space, stops) = match first.first() {
+            Some(word) if css_color(word.text).is_some() => (default_shape(kind), ColorSpace::Srgb, &args[..]),
+            _ if kind == "linear-gradient" => {
+                let (direction, space) = parse_linear(first)?;
+                (Shape::Linear(direction), space, &args[1..])
+            },
+            _ => {
+                let (shape, space) = parse_radial(first)?;
+                (shape, space, &args[1..])
+            },
+        };
+        if stops.is_empty() {
+            return Err(ParseError::new("Expected color stops", close..close + 1));
+        }
+
+        let mut gradient = build(parse_stops(stops)?).with_space(space);
+        if repeating {
+            gradient = gradient.repeating();
+        }
+        Ok(Self { shape, gradient })
+    }
+
+    // how far along the gradient the point (`x`, `y`) of a `width` x `height` box is
+    pub fn position(&self, x: f32, y: f32, width: f32, height: f32) -> f32 {
+        match self.shape {
+            Shape::Linear(direction) => {
+                let angle = match direction {
+                    Direction::Angle(degrees) => degrees.to_radians(),
+                    // perpendicular to the diagonal between the other two corners
+                    Direction::Corner(h, v) => (h * height).atan2(-v * width),
+                };
+                let (dx, dy) = (angle.sin(), -angle.cos());
+                // the line is as long as it takes for both ends to reach a corner
+                let length = (width * dx).abs() + (height * dy).abs();
+                ((x - width / 2.0) * dx + (y - height / 2.0) * dy) / length + 0.5
+            },
+            Shape::Radial { circle, extent, center } => {
+                let (cx, cy) = (center.0 * width, center.1 * height);
+                let (left, right, top, bottom) = (cx.abs(), (width - cx).abs(), cy.abs(), (height - cy).abs());
+                let (near_x, far_x, near_y, far_y) = (left.min(right), left.max(right), top.min(bottom), top.max(bottom));
+                let (rx, ry) = match (extent, circle) {
+                    (Extent::ClosestSide, true) => (near_x.min(near_y), near_x.min(near_y)),
+                    (Extent::ClosestSide, false) => (near_x, near_y),
+                    (Extent::FarthestSide, true) => (far_x.max(far_y), far_x.max(far_y)),
+                    (Extent::FarthestSide, false) => (far_x, far_y),
+                    (Extent::ClosestCorner, true) => (near_x.hypot(near_y), near_x.hypot(near_y)),
+                    (Extent::FarthestCorner, true) => (far_x.hypot(far_y), far_x.hypot(far_y)),
+                    // an ellipse through the corner, shaped like the one touching the sides
+                    (Extent::ClosestCorner, false) => (near_x * 2f32.sqrt(), near_y * 2f32.sqrt()),
+                    (Extent::FarthestCorner, false) => (far_x * 2f32.sqrt(), far_y * 2f32.sqrt()),
+                    (Extent::Percent(px, py), _) => (px * width, py * height),
+                };
+                ((x - cx) / rx.max(1e-6)).hypot((y - cy) / ry.max(1e-6))
+            },
+        }
+    }
+
+    pub fn sample(&self, x: f32, y: f32, width: f32, height: f32) -> (u8, u8, u8) {
+        self.gradient.at(self.position(x, y, width, height))
+    }
+}
+
+fn default_shape(kind: &str) -> Shape {
+    if kind == "linear-gradient" {
+        Shape::Linear(Direction::Angle(180.0))
+    } else {
+        Shape::Radial { circle: false, extent: Extent::FarthestCorner, center: (0.5, 0.5) }
+    }
+}
+
+// the `)` that closes the `(` at `open`
+fn closing_paren(input: &str, open: usize) -> Result<usize, ParseError> {
+    let mut depth = 0;
+    for (i, c) in input[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(open + i);
+                }
+            },
+            _ => {},
+        }
+    }
+    Err(ParseError::new("Unclosed `(`", open..open + 1))
+}
+
+// the words of each comma separated argument in `range`, none of them empty
+fn split_args(input: &str, range: Range<usize>) -> Result<Vec<Vec<Word<'_>>>, ParseError> {
+    let mut commas = Vec::new();
+    let mut depth = 0;
+    for (i, c) in input[range.clone()].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => commas.push(range.start + i),
+            _ => {},
+        }
+    }
+
+    let bounds: Vec<usize> = std::iter::once(range.start).chain(commas.iter().map(|c| c + 1)).collect();
+    let ends: Vec<usize> = commas.iter().copied().chain(std::iter::once(range.end)).collect();
+    let mut args = Vec::new();
+    for (i, (&from, &to)) in bounds.iter().zip(&ends).enumerate() {
+        let words = words(input, from..to);
+        if words.is_empty() {
+            // point at the comma next to the gap, or at `()` when there are no arguments
+            return Err(match (commas.get(i), i.checked_sub(1).and_then(|j| commas.get(j))) {
+                (Some(&c), _) | (None, Some(&c)) => ParseError::new("Expected a color stop", c..c + 1),
+                (None, None) => ParseError::new("Expected color stops", range.start - 1..range.end + 1),
+            });
+        }
+        args.push(words);
+    }
+    Ok(args)
+}
+
+fn words(input: &str, range: Range<usize>) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    for (i, c) in input[range.clone()].char_indices() {
+        let at = range.start + i;
+        match c {
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(from) = start.take() {
+                    words.push(Word { text: &input[from..at], span: from..at });
+                }
+                continue;
+            },
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {},
+        }
+        start.get_or_insert(at);
+    }
+    if let Some(from) = start {
+        words.push(Word { text: &input[from..range.end], span: from..range.end });
+    }
+    words
+}
+
+fn css_color(text: &str) -> Option<(u8, u8, u8)> {
+    let lower = text.to_lowercase();
+    match NAMED.iter().find(|(name, _)| *name == lower) {
+        Some(&(_, rgb)) => Some(rgb),
+        // ANSI codes aren't colors in CSS
+        None if lower.parse::<u8>().is_ok() => None,
+        None => parse_color(text).map(|c| c.rgb()),
+    }
+}
+
+// a number and the unit after it: `50%` is (50, "%")
+fn dimension(text: &str) -> Option<(f32, &str)> {
+    let end = text
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map_or(text.len(), |(i, _)| i);
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+// a percentage as a fraction; a bare 0 counts as 0%
+fn percentage(word: &Word) -> Result<f32, ParseError> {
+    match dimension(word.text) {
+        Some((value, "%")) => Ok(value / 100.0),
+        Some((0.0, "")) => Ok(0.0),
+        Some((_, unit)) if !unit.is_empty() => {
+            Err(ParseError::new(format!("Lengths like `{}` aren't supported (use a percentage)", word.text), word.span.clone()))
+        },
+        _ => Err(ParseError::new(format!("Expected a percentage, found `{}`", word.text), word.span.clone())),
+    }
+}
+
+fn angle(text: &str) -> Option<f32> {
+    let (value, unit) = dimension(text)?;
+    match unit.to_lowercase().as_str() {
+        "deg" => Some(value),
+        "grad" => Some(value * 0.9),
+        "rad" => Some(value.to_degrees()),
+        "turn" => Some(value * 360.0),
+        "" if value == 0.0 => Some(0.0),
+        _ => None,
+    }
+}
+
+fn unexpected(word: &Word, expected: &str) -> ParseError {
+    ParseError::new(format!("Unexpected `{}` (expected {})", word.text, expected), word.span.clone())
+}
+
+// `in <space> [<method> hue]` from the word after `in`; also how many words it took
+fn parse_space(words: &[Word], keyword: &Word) -> Result<(ColorSpace, usize), ParseError> {
+    let Some(name) = words.first() else {
+        return Err(ParseError::new("Expected a color space after `in`", keyword.span.clone()));
+    };
+    let space = match name.text.to_lowercase().as_str() {
+        "srgb" => ColorSpace::Srgb,
+        "srgb-linear" => ColorSpace::LinearSrgb,
+        "oklab" => ColorSpace::Oklab,
+        "oklch" => ColorSpace::Oklch(HueMethod::Shorter),
+        _ => return Err(unexpected(name, "srgb, srgb-linear, oklab or oklch")),
+    };
+
+    let method = match words.get(1).map(|w| w.text.to_lowercase()).as_deref() {
+        Some("shorter") => HueMethod::Shorter,
+        Some("longer") => HueMethod::Longer,
+        Some("increasing") => HueMethod::Increasing,
+        Some("decreasing") => HueMethod::Decreasing,
+        _ => return Ok((space, 1)),
+    };
+    if !matches!(space, ColorSpace::Oklch(_)) {
+        return Err(ParseError::new(format!("`{}` has no hue to interpolate", name.text), words[1].span.clone()));
+    }
+    match words.get(2) {
+        Some(word) if word.text.eq_ignore_ascii_case("hue") => Ok((ColorSpace::Oklch(method), 3)),
+        _ => Err(ParseError::new(format!("Expected `hue` after `{}`", words[1].text), words[1].span.clone())),
+    }
+}
+
+// `[<angle> | to <side or corner>] || in <color space>`
+fn parse_linear(words: &[Word]) -> Result<(Direction, ColorSpace), ParseError> {
+    let (mut direction, mut space) = (None, None);
+    let mut i = 0;
+    while let Some(word) = words.get(i) {
+        let lower = word.text.to_lowercase();
+        if lower == "in" && space.is_none() {
+            let (parsed, used) = parse_space(&words[i + 1..], word)?;
+            space = Some(parsed);
+            i += 1 + used;
+        } else if lower == "to" && direction.is_none() {
+            let (mut h, mut v) = (None, None);
+            i += 1;
+            while let Some(side) = words.get(i) {
+                let (axis, value) = match side.text.to_lowercase().as_str() {
+                    "left" => (&mut h, -1.0),
+                    "right" => (&mut h, 1.0),
+                    "top" => (&mut v, -1.0),
+                    "bottom" => (&mut v, 1.0),
+                    _ => break,
+                };
+                if axis.is_some() {
+                    return Err(ParseError::new(format!("`{}` can't follow another side on the same axis", side.text), side.span.clone()));
+                }
+                *axis = Some(value);
+                i += 1;
+            }
+            direction = Some(match (h, v) {
+                (Some(h), Some(v)) => Direction::Corner(h, v),
+                (Some(h), None) => Direction::Angle(if h > 0.0 { 90.0 } else { 270.0 }),
+                (None, Some(v)) => Direction::Angle(if v > 0.0 { 180.0 } else { 0.0 }),
+                (None, None) => return Err(ParseError::new("Expected a side after `to` (left, right, top or bottom)", word.span.clone())),
+            });
+        } else if let Some(degrees) = angle(&lower).filter(|_| direction.is_none()) {
+            direction = Some(Direction::Angle(degrees));
+            i += 1;
+        } else {
+            let message = if css_color(word.text).is_some() { "an angle or `to <side>` first, then a comma" } else { "a color, an angle, `to <side>` or `in <color space>`" };
+            return Err(unexpected(word, message));
+        }
+    }
+    Ok((direction.unwrap_or(Direction::Angle(180.0)), space.unwrap_or(ColorSpace::Srgb)))
+}
+
+// `[circle | ellipse] || [<extent> | <percentage>{2}]` then `[at <position>]`, and `in <color space>`
+fn parse_radial(words: &[Word]) -> Result<(Shape, ColorSpace), ParseError> {
+    let (mut circle, mut extent, mut center, mut space) = (None, None, None, None);
+    let mut i = 0;
+    while let Some(word) = words.get(i) {
+        let lower = word.text.to_lowercase();
+        let keyword = match lower.as_str() {
+            "closest-side" => Some(Extent::ClosestSide),
+            "farthest-side" => Some(Extent::FarthestSide),
+            "closest-corner" => Some(Extent::ClosestCorner),
+            "farthest-corner" => Some(Extent::FarthestCorner),
+            _ => None,
+        };
+        if lower == "in" && space.is_none() {
+            let (parsed, used) = parse_space(&words[i + 1..], word)?;
+            space = Some(parsed);
+            i += 1 + used;
+        } else if (lower == "circle" || lower == "ellipse") && circle.is_none() && center.is_none() {
+            circle = Some(lower == "circle");
+            i += 1;
+        } else if let Some(keyword) = keyword.filter(|_| extent.is_none() && center.is_none()) {
+            extent = Some(keyword);
+            i += 1;
+        } else if dimension(word.text).is_some() && extent.is_none() && center.is_none() {
+            let x = percentage(word)?;
+            let Some(next) = words.get(i + 1).filter(|w| dimension(w.text).is_some()) else {
+                return Err(ParseError::new("An ellipse's size takes two percentages, its width and height", word.span.clone()));
+            };
+            extent = Some(Extent::Percent(x, percentage(next)?));
+            i += 2;
+        } else if lower == "at" && center.is_none() {
+            let (position, used) = parse_position(&words[i + 1..], word)?;
+            center = Some(position);
+            i += 1 + used;
+        } else {
+            return Err(unexpected(word, "a color, circle, ellipse, a size, `at <position>` or `in <color space>`"));
+        }
+    }
+
+    if circle == Some(true) && let Some(Extent::Percent(..)) = extent {
+        let word = words.iter().find(|w| dimension(w.text).is_some()).unwrap_or(&words[0]);
+        return Err(ParseError::new("A circle's size can't be a percentage", word.span.clone()));
+    }
+    let shape = Shape::Radial {
+        circle: circle.unwrap_or(false),
+        extent: extent.unwrap_or(Extent::FarthestCorner),
+        center: center.unwrap_or((0.5, 0.5)),
+    };
+    Ok((shape, space.unwrap_or(ColorSpace::Srgb)))
+}
+
+// one or two of left, center, right, top, bottom or a percentage after `at`
+fn parse_position(words: &[Word], keyword: &Word) -> Result<((f32, f32), usize), ParseError> {
+    // value, and whether it can be across (Some(true)), down (Some(false)) or either
+    let part = |word: &Word| -> Option<(f32, Option<bool>)> {
+        match word.text.to_lowercase().as_str() {
+            "left" => Some((0.0, Some(true))),
+            "right" => Some((1.0, Some(true))),
+            "top" => Some((0.0, Some(false))),
+            "bottom" => Some((1.0, Some(false))),
+            "center" => Some((0.5, None)),
+            _ => percentage(word).ok().map(|p| (p, None)),
+        }
+    };
+    let parts: Vec<(f32, Option<bool>)> = words.iter().take(2).map_while(part).collect();
+    match parts.as_slice() {
+        [] => match words.first() {
+            Some(word) if dimension(word.text).is_some() => Err(percentage(word).unwrap_err()),
+            Some(word) => Err(unexpected(word, "left, center, right, top, bottom or a percentage")),
+            None => Err(ParseError::new("Expected a position after `at`", keyword.span.clone())),
+        },
+        [(v, Some(false))] => Ok(((0.5, *v), 1)),
+        [(v, _)] => Ok(((*v, 0.5), 1)),
+        [(a, a_axis), (b, b_axis)] => {
+            // keywords can come in either order, percentages are always across then down
+            let (x, y) = if *a_axis == Some(false) || *b_axis == Some(true) { ((b, b_axis), (a, a_axis)) } else { ((a, a_axis), (b, b_axis)) };
+            if *x.1 == Some(false) || *y.1 == Some(true) {
+                return Err(ParseError::new("A position takes one horizontal and one vertical part", words[0].span.start..words[1].span.end));
+            }
+            Ok(((*x.0, *y.0), 2))
+        },
+        _ => unreachable!(),
+    }
+}
+
+// `<color> [<percentage> [<percentage>]]` or a `<percentage>` hint in each argument
+fn parse_stops(args: &[Vec<Word>]) -> Result<Vec<Item>, ParseError> {
+    let mut items = Vec::new();
+    for (n, words) in args.iter().enumerate() {
+        let first = &words[0];
+        let Some(color) = css_color(first.text) else {
+            if words.len() == 1 && dimension(first.text).is_some() {
+                let hint = percentage(first)?;
+                let between = matches!(items.last(), Some(Item::Stop(..))) && n + 1 < args.len();
+                if !between {
+                    return Err(ParseError::new("A color hint needs a color stop on each side", first.span.clone()));
+                }
+                items.push(Item::Hint(hint));
+                continue;
+            }
+            return Err(ParseError::new(format!("Unknown color `{}`", first.text), first.span.clone()));
+        };
+        if let Some(extra) = words.get(3) {
+            return Err(unexpected(extra, "a comma; a color stop takes at most two positions"));
+        }
+        match &words[1..] {
+            [] => items.push(Item::Stop(color, None)),
+            positions => {
+                for word in positions {
+                    items.push(Item::Stop(color, Some(percentage(word)?)));
+                }
+            },
+        }
+    }
+    Ok(items)
+}
+
+// stops and hints with every position filled in the way CSS does it
+fn build(items: Vec<Item>) -> Gradient {
+    let mut colors = Vec::new();
+    let mut positions: Vec<Option<f32>> = Vec::new();
+    let mut hints: Vec<Option<f32>> = Vec::new(); // absolute for now
+    for item in items {
+        match item {
+            Item::Stop(color, position) => {
+                if !colors.is_empty() && hints.len() < colors.len() {
+                    hints.push(None);
+                }
+                colors.push(color);
+                positions.push(position);
+            },
+            Item::Hint(position) => hints.push(Some(position)),
+        }
+    }
+
+    // the ends default to 0% and 100%
+    if let Some(first) = positions.first_mut() {
+        first.get_or_insert(0.0);
+    }
+    if let Some(last) = positions.last_mut() {
+        last.get_or_insert(1.0);
+    }
+    // nothing sits before anything positioned ahead of it
+    let mut max = f32::MIN;
+    for (i, position) in positions.iter_mut().enumerate() {
+        if let Some(p) = position {
+            *p = p.max(max);
+            max = *p;
+        }
+        if let Some(Some(h)) = hints.get_mut(i) {
+            *h = h.max(max);
+            max = *h;
+        }
+    }
+    // runs without a position are spread evenly between the stops around them
+    let mut i = 1;
+    while i < positions.len() {
+        if positions[i].is_none() {
+            let end = (i..positions.len()).find(|&j| positions[j].is_some()).unwrap_or(positions.len() - 1);
+            let (from, to) = (positions[i - 1].unwrap_or(0.0), positions[end].unwrap_or(1.0));
+            for (k, p) in positions.iter_mut().enumerate().take(end).skip(i) {
+                *p = Some(from + (to - from) * (k - i + 1) as f32 / (end - i + 1) as f32);
+            }
+            i = end;
+        }
+        i += 1;
+    }
+
+    let positions: Vec<f32> = positions.into_iter().map(|p| p.unwrap_or(0.0)).collect();
+    // hints as a fraction of their segment
+    let hints = hints
+        .iter()
+        .enumerate()
+        .map(|(i, hint)| {
+            let (start, end) = (positions[i], positions[i + 1]);
+            hint.filter(|_| end > start).map(|h| ((h - start) / (end - start)).clamp(0.0, 1.0))
+        })
+        .collect();
+    Gradient::new(colors).with_positions(positions).with_hints(hints)
+}
+
+// the gradient across the whole terminal, as a browser paints a box of that shape
+pub fn print_fill(css: &CssGradient, depth: ColorDepth, mode: Dither) {
+    let (columns, rows) = terminal_size().map(|(w, h)| (w as usize, h as usize)).unwrap_or((80, 24));
+    // leave a line for the prompt
+    let rows = rows.saturating_sub(1).max(1);
+
+    if let Some(protocol) = graphics::protocol() {
+        let (cell_w, cell_h) = cell_pixels();
+        let mut raster = Raster::new(columns * cell_w, rows * cell_h);
+        let (width, height) = (raster.width as f32, raster.height as f32);
+        let stride = raster.width;
+        for (i, pixel) in raster.pixels.iter_mut().enumerate() {
+            *pixel = Some(css.sample((i % stride) as f32 + 0.5, (i / stride) as f32 + 0.5, width, height));
+        }
+        print_raster(protocol, &raster, columns, rows);
+        return;
+    }
+
+    // two pixels per cell, each half as tall as a cell
+    let pixel_aspect = cell_aspect().unwrap_or(DEFAULT_CELL_ASPECT) / 2.0;
+    let (width, height) = (columns, rows * 2);
+    let (box_w, box_h) = (width as f32, height as f32 * pixel_aspect);
+    let mut pixels: Vec<[f32; 3]> = (0..width * height)
+        .map(|i| {
+            let (x, y) = ((i % width) as f32 + 0.5, ((i / width) as f32 + 0.5) * pixel_aspect);
+            let (r, g, b) = css.sample(x, y, box_w, box_h);
+            [r as f32, g as f32, b as f32]
+        })
+        .collect();
+    dither(&mut pixels, width, depth, mode);
+    print_pixels(&pixels, width, height, depth);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stops_positions_and_hints() {
+        let css = CssGradient::parse("linear-gradient(90deg, #f5a9b8 0%, #5bcefa 50%, #fff 100%)").unwrap();
+        assert_eq!(css.shape, Shape::Linear(Direction::Angle(90.0)));
+        assert_eq!(css.gradient.at(0.0), (0xf5, 0xa9, 0xb8));
+        assert_eq!(css.gradient.at(0.5), (0x5b, 0xce, 0xfa));
+        assert_eq!(css.gradient.at(1.0), (255, 255, 255));
+
+        // unpositioned stops spread out, two positions make a band, a hint moves the midpoint
+        let css = CssGradient::parse("repeating-linear-gradient(to right in oklch, red, lime 20% 40%, 50%, blue)").unwrap();
+        assert_eq!(css.gradient.at(0.0), (255, 0, 0));
+        assert_eq!(css.gradient.at(0.3), (0, 255, 0));
+        assert_eq!(css.gradient.at(1.0), (0, 0, 255));
+
+        let radial = CssGradient::parse("radial-gradient(circle closest-side at left 30%, black, white)").unwrap();
+        assert_eq!(radial.shape, Shape::Radial { circle: true, extent: Extent::ClosestSide, center: (0.0, 0.3) });
+    }
+
+    #[test]
+    fn points_at_the_offending_token() {
+        fn error(input: &str) -> (String, &str) {
+            let e = CssGradient::parse(input).err().unwrap();
+            (e.message, &input[e.span])
+        }
+        assert_eq!(error("linear-gradient(90deg, #ggg, blue)"), ("Unknown color `#ggg`".to_string(), "#ggg"));
+        assert_eq!(error("linear-gradient(red 10px, blue)").1, "10px");
+        assert_eq!(error("linear-gradient(in hsl, red, blue)").1, "hsl");
+        assert_eq!(error("linear-gradient(red, 50%)").1, "50%");
+        assert_eq!(error("linear-gradient(red, , blue)").1, ",");
+        assert_eq!(error("conic-gradient(red, blue)").1, "conic-gradient");
+        assert_eq!(error("linear-gradient(to left right, red, blue)").1, "right");
+        assert_eq!(error("linear-gradient(red, blue").1, "(");
+    }
+
+    #[test]
+    fn maps_points_of_a_box() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let right = CssGradient::parse("linear-gradient(to right, black, white)").unwrap();
+        assert!(close(right.position(0.0, 5.0, 100.0, 10.0), 0.0) && close(right.position(75.0, 0.0, 100.0, 10.0), 0.75));
+        // towards a corner, the opposite corners sit at the middle
+        let corner = CssGradient::parse("linear-gradient(to top right, black, white)").unwrap();
+        assert!(close(corner.position(0.0, 0.0, 100.0, 10.0), 0.5) && close(corner.position(100.0, 0.0, 100.0, 10.0), 1.0));
+
+        let radial = CssGradient::parse("radial-gradient(circle farthest-side, black, white)").unwrap();
+        assert!(close(radial.position(50.0, 5.0, 100.0, 10.0), 0.0) && close(radial.position(100.0, 5.0, 100.0, 10.0), 1.0));
+    }
+}
@@ -0,0 +1,142 @@
+// CSS easing functions, mapping progress 0..=1 through a segment to progress along its colors
+
+// where `steps()` jumps: before the first step, after the last, both or neither
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Jump {
+    Start,
+    End,
+    Both,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    CubicBezier(f32, f32, f32, f32),
+    Smoothstep,
+    Steps(u32, Jump),
+}
+
+impl Easing {
+    // CSS syntax: `linear`, `ease`, `ease-in`, `ease-out`, `ease-in-out`, `step-start`,
+    // `step-end`, `cubic-bezier(x1, y1, x2, y2)` and `steps(n[, jump-start|jump-end|
+    // jump-both|jump-none|start|end])`, plus `smoothstep`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        match name.as_str() {
+            "linear" => return Some(Self::Linear),
+            "ease" => return Some(Self::CubicBezier(0.25, 0.1, 0.25, 1.0)),
+            "ease-in" => return Some(Self::CubicBezier(0.42, 0.0, 1.0, 1.0)),
+            "ease-out" => return Some(Self::CubicBezier(0.0, 0.0, 0.58, 1.0)),
+            "ease-in-out" => return Some(Self::CubicBezier(0.42, 0.0, 0.58, 1.0)),
+            "smoothstep" => return Some(Self::Smoothstep),
+            "step-start" => return Some(Self::Steps(1, Jump::Start)),
+            "step-end" => return Some(Self::Steps(1, Jump::End)),
+            _ => {},
+        }
+
+        let (function, args) = name.strip_suffix(')')?.split_once('(')?;
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+        match (function.trim(), args.as_slice()) {
+            ("cubic-bezier", [x1, y1, x2, y2]) => {
+                let [x1, y1, x2, y2] = [x1, y1, x2, y2].map(|v| v.parse::<f32>().ok());
+                let (x1, y1, x2, y2) = (x1?, y1?, x2?, y2?);
+                // x has to stay within 0..=1 for the curve to be a function of time
+                ((0.0..=1.0).contains(&x1) && (0.0..=1.0).contains(&x2)).then_some(Self::CubicBezier(x1, y1, x2, y2))
+            },
+            ("steps", [count, rest @ ..]) => {
+                let count: u32 = count.parse().ok()?;
+                let jump = match rest {
+                    [] | ["jump-end"] | ["end"] => Jump::End,
+                    ["jump-start"] | ["start"] => Jump::Start,
+                    ["jump-both"] => Jump::Both,
+                    ["jump-none"] => Jump::None,
+                    _ => return None,
+                };
+                let min = if jump == Jump::None { 2 } else { 1 };
+                (count >= min).then_some(Self::Steps(count, jump))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            Self::Smoothstep => t * t * (3.0 - 2.0 * t),
+            Self::Steps(count, jump) => {
+                let n = count as f32;
+                let mut step = (t * n).floor();
+                if matches!(jump, Jump::Start | Jump::Both) {
+                    step += 1.0;
+                }
+                let jumps = match jump {
+                    Jump::Start | Jump::End => n,
+                    Jump::Both => n + 1.0,
+                    Jump::None => n - 1.0,
+                };
+                (step / jumps).clamp(0.0, 1.0)
+            },
+        }
+    }
+}
+
+// y of the curve from (0, 0) to (1, 1) through control points (x1, y1) and (x2, y2) at x = t
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, t: f32) -> f32 {
+    let curve = |p1: f32, p2: f32, u: f32| 3.0 * (1.0 - u) * (1.0 - u) * u * p1 + 3.0 * (1.0 - u) * u * u * p2 + u * u * u;
+    let slope = |p1: f32, p2: f32, u: f32| 3.0 * (1.0 - u) * (1.0 - u) * p1 + 6.0 * (1.0 - u) * u * (p2 - p1) + 3.0 * u * u * (1.0 - p2);
+
+    // Newton's method from u = t usually lands in a few steps; bisect when the slope is flat
+    let mut u = t;
+    for _ in 0..8 {
+        let error = curve(x1, x2, u) - t;
+        if error.abs() < 1e-6 {
+            return curve(y1, y2, u);
+        }
+        let d = slope(x1, x2, u);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        u = (u - error / d).clamp(0.0, 1.0);
+    }
+
+    let (mut lo, mut hi) = (0.0, 1.0);
+    u = t;
+    for _ in 0..32 {
+        if curve(x1, x2, u) < t { lo = u } else { hi = u }
+        u = (lo + hi) / 2.0;
+    }
+    curve(y1, y2, u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_css_timing_functions() {
+        assert_eq!(Easing::from_name("ease-in"), Some(Easing::CubicBezier(0.42, 0.0, 1.0, 1.0)));
+        assert_eq!(Easing::from_name("cubic-bezier(0.1, -0.5, 0.9, 1.5)"), Some(Easing::CubicBezier(0.1, -0.5, 0.9, 1.5)));
+        assert_eq!(Easing::from_name("steps(4, jump-both)"), Some(Easing::Steps(4, Jump::Both)));
+        assert_eq!(Easing::from_name("steps(3)"), Some(Easing::Steps(3, Jump::End)));
+        assert_eq!(Easing::from_name("cubic-bezier(1.5, 0, 0, 1)"), None);
+        assert_eq!(Easing::from_name("steps(1, jump-none)"), None);
+        assert_eq!(Easing::from_name("bounce"), None);
+    }
+
+    #[test]
+    fn eases_like_a_browser() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+        // values from the CSS easing spec's reference implementation
+        let ease = Easing::from_name("ease").unwrap();
+        assert!(close(ease.apply(0.5), 0.8024));
+        assert!(close(Easing::from_name("ease-in").unwrap().apply(0.25), 0.0935));
+        assert!(close(Easing::Smoothstep.apply(0.25), 0.15625));
+        assert_eq!([0.0, 0.3, 0.99, 1.0].map(|t| Easing::Steps(4, Jump::End).apply(t)), [0.0, 0.25, 0.75, 1.0]);
+        assert_eq!([0.0, 0.3, 1.0].map(|t| Easing::Steps(4, Jump::Start).apply(t)), [0.25, 0.5, 1.0]);
+        assert_eq!([0.1, 0.5, 0.9].map(|t| Easing::Steps(3, Jump::None).apply(t)), [0.0, 0.5, 1.0]);
+        assert_eq!([0.0, 0.5].map(|t| Easing::Steps(3, Jump::Both).apply(t)), [0.25, 0.5]);
+    }
+}
@@ -0,0 +1,39 @@
+// formats colors can be written out in for other tools
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Kitty,
+    Alacritty,
+    Xresources,
+    Css,
+    Json,
+    Hex,
+    Rgb,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "kitty" => Some(Self::Kitty),
+            "alacritty" => Some(Self::Alacritty),
+            "xresources" | "xrdb" => Some(Self::Xresources),
+            "css" => Some(Self::Css),
+            "json" => Some(Self::Json),
+            "hex" => Some(Self::Hex),
+            "rgb" => Some(Self::Rgb),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kitty => "kitty",
+            Self::Alacritty => "alacritty",
+            Self::Xresources => "xresources",
+            Self::Css => "css",
+            Self::Json => "json",
+            Self::Hex => "hex",
+            Self::Rgb => "rgb",
+        }
+    }
+}
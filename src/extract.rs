@@ -0,0 +1,199 @@
+use std::fmt::Write as _;
+
+use crate::{
+    color::{to_hex, ColorValue, Oklch},
+    export::ExportFormat,
+    image::Image,
+    palette::push_background,
+    rng::SimpleRng,
+    terminal::terminal_size,
+};
+
+// pixels clustered at most; bigger images are sampled on an even stride
+const MAX_SAMPLES: usize = 40_000;
+const MAX_ITERATIONS: usize = 32;
+// pixels more transparent than this don't count towards the palette
+const MIN_ALPHA: u8 = 128;
+// clusters closer than this (half a just noticeable difference) are reported as one
+const MERGE_DISTANCE: f32 = 0.01;
+
+type Lab = [f32; 3];
+
+fn distance_sq(a: Lab, b: Lab) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+fn nearest(centers: &[Lab], p: Lab) -> usize {
+    (0..centers.len()).min_by(|&a, &b| distance_sq(centers[a], p).total_cmp(&distance_sq(centers[b], p))).unwrap_or(0)
+}
+
+// uniform in 0..1
+fn unit(rng: &mut SimpleRng) -> f32 {
+    (rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// k-means++: each new center is picked with probability proportional to its squared
+// distance from the centers so far, so clusters start out spread over the image
+fn initial_centers(points: &[Lab], count: usize, rng: &mut SimpleRng) -> Vec<Lab> {
+    let mut centers = vec![points[rng.next_u64() as usize % points.len()]];
+    let mut dist: Vec<f32> = points.iter().map(|&p| distance_sq(p, centers[0])).collect();
+    while centers.len() < count {
+        let total: f32 = dist.iter().sum();
+        if total <= 0.0 {
+            break; // fewer distinct colors than asked for
+        }
+        let mut target = unit(rng) * total;
+        let pick = dist.iter().position(|&d| {
+            target -= d;
+            target <= 0.0
+        });
+        let center = points[pick.unwrap_or(points.len() - 1)];
+        for (d, &p) in dist.iter_mut().zip(points) {
+            *d = d.min(distance_sq(p, center));
+        }
+        centers.push(center);
+    }
+    centers
+}
+
+// the `count` dominant colors of an image, found by k-means in OKLab, with the share
+// of (opaque) pixels each covers, largest first
+pub fn dominant_colors(image: &Image, count: usize, seed: u64) -> Vec<((u8, u8, u8), f32)> {
+    let stride = image.pixels.len().div_ceil(MAX_SAMPLES).max(1);
+    let points: Vec<Lab> = image
+        .pixels
+        .iter()
+        .step_by(stride)
+        .filter(|p| p[3] >= MIN_ALPHA)
+        .map(|p| {
+            let (l, a, b) = Oklch::from_rgb(p[0], p[1], p[2]).to_lab();
+            [l, a, b]
+        })
+        .collect();
+    if points.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let mut rng = SimpleRng::with_seed(seed);
+    let mut centers = initial_centers(&points, count, &mut rng);
+    let mut sizes = vec![0usize; centers.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut sums = vec![[0.0f64; 3]; centers.len()];
+        sizes.fill(0);
+        for &p in &points {
+            let i = nearest(&centers, p);
+            sizes[i] += 1;
+            for (sum, v) in sums[i].iter_mut().zip(p) {
+                *sum += v as f64;
+            }
+        }
+
+        let mut moved = false;
+        for ((center, sum), &size) in centers.iter_mut().zip(&sums).zip(&sizes) {
+            if size == 0 {
+                continue;
+            }
+            let mean = sum.map(|s| (s / size as f64) as f32);
+            moved |= distance_sq(*center, mean) > 1e-10;
+            *center = mean;
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    // a near flat image still gets `count` centers, so fold together the ones that
+    // ended up indistinguishable, weighted by how many pixels each holds
+    let mut merged: Vec<(Lab, usize)> = Vec::new();
+    for (center, size) in centers.into_iter().zip(sizes).filter(|&(_, size)| size > 0) {
+        match merged.iter_mut().find(|(c, _)| distance_sq(*c, center) < MERGE_DISTANCE * MERGE_DISTANCE) {
+            Some((c, n)) => {
+                let total = (*n + size) as f32;
+                *c = [0, 1, 2].map(|k| (c[k] * *n as f32 + center[k] * size as f32) / total);
+                *n += size;
+            },
+            None => merged.push((center, size)),
+        }
+    }
+
+    let mut colors: Vec<((u8, u8, u8), f32)> = merged
+        .into_iter()
+        .map(|(c, size)| (Oklch::from_lab(c[0], c[1], c[2]).to_rgb(), size as f32 / points.len() as f32))
+        .collect();
+    colors.sort_by(|a, b| b.1.total_cmp(&a.1));
+    colors
+}
+
+// a strip across the terminal split by share, then a swatch per color with its hex and percentage
+pub fn print_swatches(colors: &[((u8, u8, u8), f32)], width: u8) {
+    let term_width = terminal_size().map(|(w, _)| w as usize).unwrap_or(80);
+    let mut buf = String::new();
+
+    // cumulative rounding so the strip always fills the whole width
+    let mut covered = 0.0;
+    let mut drawn = 0;
+    for &((r, g, b), share) in colors {
+        covered += share;
+        let end = (covered * term_width as f32).round() as usize;
+        push_background(&mut buf, ColorValue::Rgb(r, g, b));
+        buf.push_str(&" ".repeat(end.saturating_sub(drawn)));
+        drawn = drawn.max(end);
+    }
+    buf.push_str("\x1b[0m\n\n");
+
+    for &((r, g, b), share) in colors {
+        push_background(&mut buf, ColorValue::Rgb(r, g, b));
+        let _ = writeln!(buf, "{}\x1b[0m {} {:>5.1}%", " ".repeat(width.into()), to_hex((r, g, b)), share * 100.0);
+    }
+    print!("{}", buf);
+}
+
+// a plain hex list by default (pipes back into `clrblk -`), or CSS variables / JSON named
+// `name-1`, `name-2`... in order of dominance
+pub fn export(colors: &[((u8, u8, u8), f32)], format: Option<ExportFormat>, name: &str) -> Option<String> {
+    let mut out = String::new();
+    match format {
+        None | Some(ExportFormat::Hex) => {
+            for &(rgb, _) in colors {
+                let _ = writeln!(out, "{}", to_hex(rgb));
+            }
+        },
+        Some(ExportFormat::Css) => {
+            let _ = writeln!(out, ":root {{");
+            for (i, &(rgb, _)) in colors.iter().enumerate() {
+                let _ = writeln!(out, "  --{}-{}: {};", name, i + 1, to_hex(rgb));
+            }
+            let _ = writeln!(out, "}}");
+        },
+        Some(ExportFormat::Json) => {
+            let entries: Vec<String> = colors
+                .iter()
+                .map(|&(rgb, share)| format!("    {{ \"color\": \"{}\", \"share\": {:.4} }}", to_hex(rgb), share))
+                .collect();
+            let _ = writeln!(out, "{{\n  \"{}\": [\n{}\n  ]\n}}", name, entries.join(",\n"));
+        },
+        Some(_) => return None,
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_blocks_of_color_with_their_shares() {
+        // three quarters red, one quarter blue
+        let pixels = (0..64).map(|i| if i % 4 == 0 { [0, 0, 255, 255] } else { [255, 0, 0, 255] }).collect();
+        let image = Image { width: 8, height: 8, pixels };
+        let colors = dominant_colors(&image, 5, 0);
+        assert_eq!(colors, [((255, 0, 0), 0.75), ((0, 0, 255), 0.25)]);
+    }
+
+    #[test]
+    fn skips_transparent_pixels() {
+        let pixels = vec![[0, 255, 0, 255], [255, 255, 255, 0], [0, 255, 0, 200], [255, 255, 255, 10]];
+        let image = Image { width: 2, height: 2, pixels };
+        assert_eq!(dominant_colors(&image, 2, 7), [((0, 255, 0), 1.0)]);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{
+    color::{linear_to_srgb, srgb_to_linear, to_u8, Oklch},
+    easing::Easing,
+};
+
+// color ramps sampled at a position between 0 and 1
+
+// --easing and --hint, applied to every segment of a gradient built from the command line
+#[derive(Clone, Copy)]
+pub struct GradientShape {
+    pub easing: Easing,
+    pub hint: Option<f32>,
+}
+
+impl GradientShape {
+    pub fn apply(self, gradient: Gradient) -> Gradient {
+        let segments = gradient.stops.len().saturating_sub(1);
+        let gradient = gradient.with_easing(self.easing);
+        match self.hint {
+            Some(hint) => gradient.with_hints(vec![Some(hint); segments]),
+            None => gradient,
+        }
+    }
+}
+
+// which way around the hue circle OKLCH mixing goes, as CSS `<hue-interpolation-method>`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HueMethod {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+// the space two stops are mixed in, as CSS `in <color-space>`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    LinearSrgb,
+    Oklab,
+    Oklch(HueMethod),
+}
+
+impl ColorSpace {
+    fn mix(self, a: (u8, u8, u8), b: (u8, u8, u8), f: f32) -> (u8, u8, u8) {
+        let lerp = |x: f32, y: f32| x + (y - x) * f;
+        match self {
+            Self::Srgb => {
+                let mix = |x: u8, y: u8| lerp(x as f32, y as f32).round() as u8;
+                (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+            },
+            Self::LinearSrgb => {
+                let mix = |x: u8, y: u8| to_u8(linear_to_srgb(lerp(srgb_to_linear(x as f32 / 255.0), srgb_to_linear(y as f32 / 255.0))));
+                (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+            },
+            Self::Oklab => {
+                let (l1, a1, b1) = Oklch::from_rgb(a.0, a.1, a.2).to_lab();
+                let (l2, a2, b2) = Oklch::from_rgb(b.0, b.1, b.2).to_lab();
+                Oklch::from_lab(lerp(l1, l2), lerp(a1, a2), lerp(b1, b2)).to_rgb()
+            },
+            Self::Oklch(method) => {
+                let (mut x, mut y) = (Oklch::from_rgb(a.0, a.1, a.2), Oklch::from_rgb(b.0, b.1, b.2));
+                // grays have no hue of their own and take the other color's
+                if x.c < 1e-3 {
+                    x.h = y.h;
+                }
+                if y.c < 1e-3 {
+                    y.h = x.h;
+                }
+                let mut delta = y.h - x.h;
+                match method {
+                    HueMethod::Shorter if delta > 180.0 => delta -= 360.0,
+                    HueMethod::Shorter if delta < -180.0 => delta += 360.0,
+                    HueMethod::Longer if delta > 0.0 && delta < 180.0 => delta -= 360.0,
+                    HueMethod::Longer if delta > -180.0 && delta <= 0.0 => delta += 360.0,
+                    HueMethod::Increasing if delta < 0.0 => delta += 360.0,
+                    HueMethod::Decreasing if delta > 0.0 => delta -= 360.0,
+                    _ => {},
+                }
+                Oklch { l: lerp(x.l, y.l), c: lerp(x.c, y.c), h: (x.h + delta * f).rem_euclid(360.0) }.to_rgb()
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Gradient {
+    stops: Vec<(u8, u8, u8)>,
+    positions: Vec<f32>, // where each stop sits, ascending
+    hints: Vec<Option<f32>>, // per segment, how far along it the halfway color falls
+    easing: Easing, // applied within each segment
+    space: ColorSpace,
+    cyclic: bool, // the last stop leads back into the first
+    repeating: bool, // the stops repeat beyond the first and last position
+}
+
+impl Gradient {
+    pub const NAMES: [&str; 5] = ["rainbow", "grayscale", "fire", "ocean", "sunset"];
+
+    // evenly spaced stops; a single stop is a solid color
+    pub fn new(stops: Vec<(u8, u8, u8)>) -> Self {
+        let last = stops.len().saturating_sub(1).max(1) as f32;
+        let positions = (0..stops.len()).map(|i| i as f32 / last).collect();
+        let hints = vec![None; stops.len().saturating_sub(1)];
+        Self { stops, positions, hints, easing: Easing::Linear, space: ColorSpace::Srgb, cyclic: false, repeating: false }
+    }
+
+    // where each stop sits, ascending; several stops at one position make a hard edge
+    pub fn with_positions(mut self, positions: Vec<f32>) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    // CSS color hints: where between two stops their halfway color falls, 0.5 being linear
+    pub fn with_hints(mut self, hints: Vec<Option<f32>>) -> Self {
+        self.hints = hints;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_space(mut self, space: ColorSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    // CSS `repeating-*-gradient`: the span from the first stop to the last repeats forever
+    pub fn repeating(mut self) -> Self {
+        self.repeating = true;
+        self
+    }
+
+    // the six phases of `print_rainbow`, looping back to red
+    pub fn rainbow() -> Self {
+        Self {
+            cyclic: true,
+            ..Self::new(vec![(255, 0, 0), (255, 255, 0), (0, 255, 0), (0, 255, 255), (0, 0, 255), (255, 0, 255), (255, 0, 0)])
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rainbow" => Some(Self::rainbow()),
+            "grayscale" | "greyscale" => Some(Self::new(vec![(0, 0, 0), (255, 255, 255)])),
+            "fire"    => Some(Self::new(vec![(64, 0, 0), (205, 30, 0), (255, 140, 0), (255, 230, 80), (255, 255, 220)])),
+            "ocean"   => Some(Self::new(vec![(0, 31, 63), (0, 116, 217), (127, 219, 255)])),
+            "sunset"  => Some(Self::new(vec![(45, 27, 105), (232, 70, 124), (255, 179, 71)])),
+            _ => None,
+        }
+    }
+
+    // cells needed for neighbouring ones to differ by at most one level in any channel
+    pub fn distinct_steps(&self) -> usize {
+        let channel = |a: u8, b: u8| (a as i16 - b as i16).unsigned_abs() as usize;
+        self.stops
+            .windows(2)
+            .map(|w| channel(w[0].0, w[1].0).max(channel(w[0].1, w[1].1)).max(channel(w[0].2, w[1].2)))
+            .sum::<usize>()
+            .max(1)
+    }
+
+    pub fn is_cyclic(&self) -> bool {
+        self.cyclic
+    }
+
+    // color at `t`, clamped to the ends unless repeating
+    pub fn at(&self, t: f32) -> (u8, u8, u8) {
+        let Some(&first) = self.stops.first() else {
+            return (0, 0, 0);
+        };
+        if self.stops.len() == 1 {
+            return first;
+        }
+
+        let (first, last) = (self.positions[0], self.positions[self.positions.len() - 1]);
+        let t = if self.repeating && last > first && !(first..=last).contains(&t) {
+            first + (t - first).rem_euclid(last - first)
+        } else {
+            t
+        };
+        // solid past either end, which also keeps stops sharing one position apart
+        if t < first {
+            return self.stops[0];
+        }
+        if t >= last {
+            return self.stops[self.stops.len() - 1];
+        }
+        // the last stop at or before `t`, so at a hard edge the later color wins
+        let i = self.positions[1..self.positions.len() - 1].iter().take_while(|&&p| p <= t).count();
+        let (start, end) = (self.positions[i], self.positions[i + 1]);
+        let mut f = if end > start { (t - start) / (end - start) } else { 1.0 };
+        if let Some(Some(hint)) = self.hints.get(i) {
+            // CSS: the exponent that takes `hint` to one half
+            f = match *hint {
+                h if h <= 0.0 => if f > 0.0 { 1.0 } else { 0.0 },
+                h if h >= 1.0 => if f < 1.0 { 0.0 } else { 1.0 },
+                h => f.powf(0.5f32.ln() / h.ln()),
+            };
+        }
+        self.space.mix(self.stops[i], self.stops[i + 1], self.easing.apply(f))
+    }
+
+    // color at any `t`, repeating every 1; cyclic gradients wrap around and
+    // others bounce back so there is no jump between the last and first stop
+    pub fn at_wrapped(&self, t: f32) -> (u8, u8, u8) {
+        if self.cyclic {
+            self.at(t.rem_euclid(1.0))
+        } else {
+            let t = t.rem_euclid(2.0);
+            self.at(if t > 1.0 { 2.0 - t } else { t })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_between_stops() {
+        let g = Gradient::new(vec![(0, 0, 0), (200, 100, 0), (200, 100, 255)]);
+        assert_eq!(g.at(0.0), (0, 0, 0));
+        assert_eq!(g.at(0.25), (100, 50, 0));
+        assert_eq!(g.at(0.5), (200, 100, 0));
+        assert_eq!(g.at(1.0), (200, 100, 255));
+        assert_eq!(g.at(7.0), (200, 100, 255));
+    }
+
+    #[test]
+    fn applies_hints_and_easing() {
+        // halfway between the two colors a quarter of the way along, as in `black, 25%, white`
+        let hinted = Gradient::new(vec![(0, 0, 0), (200, 200, 200)]).with_hints(vec![Some(0.25)]);
+        assert_eq!(hinted.at(0.25), (100, 100, 100));
+        assert_eq!(hinted.at(0.5), (141, 141, 141));
+
+        let stepped = Gradient::new(vec![(0, 0, 0), (200, 200, 200)]).with_easing(Easing::from_name("steps(2)").unwrap());
+        assert_eq!([0.2, 0.7, 1.0].map(|t| stepped.at(t).0), [0, 100, 200]);
+    }
+
+    #[test]
+    fn places_stops_and_repeats() {
+        // a hard edge where two stops share a position
+        let edged = Gradient::new(vec![(0, 0, 0), (200, 0, 0), (0, 0, 200)]).with_positions(vec![0.2, 0.6, 0.6]);
+        assert_eq!(edged.at(0.1), (0, 0, 0));
+        assert_eq!(edged.at(0.4), (100, 0, 0));
+        assert_eq!(edged.at(0.59), (195, 0, 0));
+        assert_eq!(edged.at(0.6), (0, 0, 200));
+
+        let stripes = Gradient::new(vec![(0, 0, 0), (200, 200, 200)]).with_positions(vec![0.0, 0.25]).repeating();
+        assert_eq!(stripes.at(0.125), stripes.at(0.875));
+        assert_eq!(stripes.at(0.5), (0, 0, 0));
+
+        // two stops at one position split the gradient in two solid halves
+        let split = Gradient::new(vec![(255, 0, 0), (0, 0, 255)]).with_positions(vec![0.5, 0.5]);
+        assert_eq!((split.at(0.0), split.at(0.49)), ((255, 0, 0), (255, 0, 0)));
+        assert_eq!((split.at(0.5), split.at(1.0)), ((0, 0, 255), (0, 0, 255)));
+    }
+
+    #[test]
+    fn mixes_in_other_color_spaces() {
+        let (red, blue) = ((255, 0, 0), (0, 0, 255));
+        assert_eq!(ColorSpace::Srgb.mix(red, blue, 0.5), (128, 0, 128));
+        assert_eq!(ColorSpace::LinearSrgb.mix(red, blue, 0.5), (188, 0, 188));
+        // the short way from red (29°) to blue (264°) passes through magenta, the long way through green
+        let short = ColorSpace::Oklch(HueMethod::Shorter).mix(red, blue, 0.5);
+        let long = ColorSpace::Oklch(HueMethod::Longer).mix(red, blue, 0.5);
+        assert!(short.0 > short.1 && short.2 > short.1, "{:?}", short);
+        assert!(long.1 > long.0 && long.1 > long.2, "{:?}", long);
+        assert_eq!(ColorSpace::Oklab.mix(red, blue, 0.0), red);
+    }
+
+    #[test]
+    fn wrapping_bounces_unless_cyclic() {
+        let g = Gradient::new(vec![(0, 0, 0), (250, 250, 250)]);
+        assert_eq!(g.at_wrapped(1.2), g.at(0.8));
+        assert_eq!(g.at_wrapped(-0.2), g.at(0.2));
+
+        let rainbow = Gradient::rainbow();
+        assert_eq!(rainbow.at_wrapped(1.25), rainbow.at(0.25));
+    }
+}
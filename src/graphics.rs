@@ -0,0 +1,318 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, BufWriter, Write},
+    sync::OnceLock,
+    time::Duration,
+};
+
+use crate::{
+    simulate,
+    terminal::{cell_size, query},
+};
+
+// Pixel output through a terminal graphics protocol, for colors finer than one per cell
+
+// cell size in pixels when the terminal doesn't report it
+const DEFAULT_CELL: (usize, usize) = (10, 20);
+// color registers a sixel image may use; xterm allows 1024, most terminals 256
+const SIXEL_REGISTERS: usize = 256;
+// base64 bytes per kitty escape sequence, the most the protocol allows
+const KITTY_CHUNK: usize = 4096;
+// how long to wait for the terminal to answer capability queries
+const QUERY_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Sixel,
+    Kitty,
+}
+
+// what --graphics asked for
+#[derive(Clone, Copy, PartialEq)]
+pub enum GraphicsMode {
+    Blocks,
+    Auto,
+    Sixel,
+    Kitty,
+}
+
+impl GraphicsMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "blocks" | "none" => Some(Self::Blocks),
+            "auto" => Some(Self::Auto),
+            "sixel" => Some(Self::Sixel),
+            "kitty" => Some(Self::Kitty),
+            _ => None,
+        }
+    }
+
+    // the protocol to draw with, asking the terminal for `auto`
+    pub fn resolve(self) -> Option<Protocol> {
+        match self {
+            Self::Blocks => None,
+            Self::Auto => detect(),
+            Self::Sixel => Some(Protocol::Sixel),
+            Self::Kitty => Some(Protocol::Kitty),
+        }
+    }
+}
+
+static PROTOCOL: OnceLock<Protocol> = OnceLock::new();
+
+// draw swatches, gradients and grids as images from now on
+pub fn install(protocol: Protocol) {
+    let _ = PROTOCOL.set(protocol);
+}
+
+pub fn protocol() -> Option<Protocol> {
+    PROTOCOL.get().copied()
+}
+
+// parameters of the DA1 reply (`ESC [ ? 62 ; 4 ; ... c`), once it has fully arrived
+fn da1_params(reply: &str) -> Option<Vec<&str>> {
+    let start = reply.find("\x1b[?")? + 3;
+    let end = start + reply[start..].find('c')?;
+    Some(reply[start..end].split(';').collect())
+}
+
+// terminal name from an XTGETTCAP reply for `TN` (`ESC P 1 + r 544e = <hex> ESC \`)
+fn tcap_name(reply: &str) -> Option<String> {
+    let start = reply.find("P1+r544e=")? + 9;
+    let hex = &reply[start..start + reply[start..].find('\x1b')?];
+    let bytes: Option<Vec<u8>> =
+        (0..hex.len() / 2).map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()).collect();
+    Some(String::from_utf8_lossy(&bytes?).into_owned())
+}
+
+// ask the terminal which protocol it speaks, preferring kitty's (it takes 24 bit color
+// as is): a kitty graphics query, an XTGETTCAP request for the terminal's name, and
+// DA1, which every terminal answers and so marks the end of the replies
+pub fn detect() -> Option<Protocol> {
+    let request = "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1bP+q544e\x1b\\\x1b[c";
+    let reply = query(request, QUERY_TIMEOUT, |r| da1_params(&String::from_utf8_lossy(r)).is_some())?;
+    let reply = String::from_utf8_lossy(&reply);
+
+    let kitty_name = tcap_name(&reply).is_some_and(|name| name.contains("kitty") || name.contains("ghostty"));
+    if reply.contains("\x1b_Gi=31;OK") || kitty_name {
+        Some(Protocol::Kitty)
+    } else if da1_params(&reply).is_some_and(|params| params.contains(&"4")) {
+        Some(Protocol::Sixel)
+    } else {
+        None
+    }
+}
+
+// width and height of a cell in pixels
+pub fn cell_pixels() -> (usize, usize) {
+    cell_size().map(|(w, h)| (w as usize, h as usize)).unwrap_or(DEFAULT_CELL)
+}
+
+// an image in memory; `None` pixels are left transparent
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Option<(u8, u8, u8)>>,
+}
+
+impl Raster {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![None; width * height] }
+    }
+
+    pub fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: (u8, u8, u8)) {
+        for row in y..(y + height).min(self.height) {
+            for col in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + col] = Some(color);
+            }
+        }
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// RGBA pixels, base64 encoded and split over as many escape sequences as it takes,
+// placed over `columns` x `rows` cells
+fn encode_kitty(raster: &Raster, columns: usize, rows: usize) -> String {
+    let mut rgba = Vec::with_capacity(raster.pixels.len() * 4);
+    for pixel in &raster.pixels {
+        match pixel {
+            Some(color) => {
+                let (r, g, b) = simulate::apply(*color);
+                rgba.extend([r, g, b, 255]);
+            },
+            None => rgba.extend([0, 0, 0, 0]),
+        }
+    }
+
+    let payload = base64(&rgba);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::with_capacity(payload.len() + chunks.len() * 16 + 64);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            let _ = write!(out, "\x1b_Ga=T,f=32,s={},v={},c={},r={},q=2,m={};", raster.width, raster.height, columns, rows, more);
+        } else {
+            let _ = write!(out, "\x1b_Gm={};", more);
+        }
+        out.push_str(str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+// one band's worth of sixels for a register, run length encoded; trailing blanks dropped
+fn push_sixel_row(out: &mut String, sixels: &[u8]) {
+    let end = sixels.iter().rposition(|&s| s != 0).map_or(0, |i| i + 1);
+    let mut i = 0;
+    while i < end {
+        let run = sixels[i..end].iter().take_while(|&&s| s == sixels[i]).count();
+        let c = (63 + sixels[i]) as char;
+        if run > 3 {
+            let _ = write!(out, "!{}{}", run, c);
+        } else {
+            (0..run).for_each(|_| out.push(c));
+        }
+        i += run;
+    }
+}
+
+// DEC sixel: up to 256 color registers (given in percent per channel), then bands six
+// pixels tall, one pass per register. Images with more colors than registers (long
+// gradients) share each register between neighbouring colors in order of appearance
+fn encode_sixel(raster: &Raster) -> String {
+    let mut index: HashMap<(u8, u8, u8), usize> = HashMap::new();
+    let mut colors = Vec::new();
+    for &(r, g, b) in raster.pixels.iter().flatten() {
+        index.entry((r, g, b)).or_insert_with(|| {
+            colors.push(simulate::apply((r, g, b)));
+            colors.len() - 1
+        });
+    }
+    let registers = colors.len().min(SIXEL_REGISTERS);
+    let register = |color: usize| color * registers / colors.len().max(1);
+
+    // P2 = 1 leaves unset pixels transparent
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", raster.width, raster.height);
+    for reg in 0..registers {
+        let (r, g, b) = colors[colors.len() * reg / registers];
+        let percent = |v: u8| (v as u32 * 100 + 127) / 255;
+        let _ = write!(out, "#{};2;{};{};{}", reg, percent(r), percent(g), percent(b));
+    }
+
+    let mut sixels = vec![vec![0u8; raster.width]; registers];
+    for band in 0..raster.height.div_ceil(6) {
+        sixels.iter_mut().for_each(|row| row.fill(0));
+        for bit in 0..6.min(raster.height - band * 6) {
+            let y = band * 6 + bit;
+            let row = &raster.pixels[y * raster.width..(y + 1) * raster.width];
+            for (x, pixel) in row.iter().enumerate() {
+                if let Some(color) = pixel {
+                    sixels[register(index[color])][x] |= 1 << bit;
+                }
+            }
+        }
+
+        if band > 0 {
+            out.push('-');
+        }
+        let mut first = true;
+        for (reg, row) in sixels.iter().enumerate().filter(|(_, row)| row.iter().any(|&s| s != 0)) {
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            let _ = write!(out, "#{}", reg);
+            push_sixel_row(&mut out, row);
+        }
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+pub fn encode(protocol: Protocol, raster: &Raster, columns: usize, rows: usize) -> String {
+    match protocol {
+        Protocol::Kitty => encode_kitty(raster, columns, rows),
+        Protocol::Sixel => encode_sixel(raster),
+    }
+}
+
+// draw an image covering `columns` x `rows` cells and move to the line below it
+pub fn print_raster(protocol: Protocol, raster: &Raster, columns: usize, rows: usize) {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(encode(protocol, raster, columns, rows).as_bytes());
+    let _ = writeln!(out);
+    let _ = out.flush();
+}
+
+// one solid color over `columns` x `rows` cells
+pub fn print_block(protocol: Protocol, color: (u8, u8, u8), columns: usize, rows: usize) {
+    let (cell_w, cell_h) = cell_pixels();
+    let mut raster = Raster::new(columns * cell_w, rows * cell_h);
+    raster.fill(0, 0, raster.width, raster.height, color);
+    print_raster(protocol, &raster, columns, rows);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64_with_padding() {
+        assert_eq!(base64(b"clrblk"), "Y2xyYmxr");
+        assert_eq!(base64(b"hex"), "aGV4");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn encodes_sixel_bands_and_transparency() {
+        let mut raster = Raster::new(5, 2);
+        raster.fill(0, 0, 5, 1, (255, 0, 0));
+        raster.fill(4, 1, 1, 1, (0, 0, 255));
+        let sixel = encode_sixel(&raster);
+        assert_eq!(sixel, "\x1bP0;1;0q\"1;1;5;2#0;2;100;0;0#1;2;0;0;100#0!5@$#1!4?A\x1b\\");
+
+        // more colors than registers share them in order
+        let mut gradient = Raster::new(600, 1);
+        for x in 0..600 {
+            gradient.fill(x, 0, 1, 1, ((x % 256) as u8, (x / 256) as u8, 0));
+        }
+        assert_eq!(encode_sixel(&gradient).matches(";2;").count(), SIXEL_REGISTERS);
+    }
+
+    #[test]
+    fn splits_kitty_payloads_into_chunks() {
+        let mut raster = Raster::new(40, 40);
+        raster.fill(0, 0, 40, 40, (1, 2, 3));
+        let kitty = encode_kitty(&raster, 4, 2);
+        // 6400 bytes of RGBA make 8536 base64 characters, so three sequences
+        assert!(kitty.starts_with("\x1b_Ga=T,f=32,s=40,v=40,c=4,r=2,q=2,m=1;AQID/wECA/8B"));
+        assert_eq!(kitty.matches("\x1b_G").count(), 3);
+        assert!(kitty.ends_with("\x1b\\") && kitty.contains("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn reads_capability_replies() {
+        let reply = "\x1bP1+r544e=787465726d2d6b69747479\x1b\\\x1b[?62;4;22c";
+        assert_eq!(tcap_name(reply).as_deref(), Some("xterm-kitty"));
+        assert_eq!(da1_params(reply), Some(vec!["62", "4", "22"]));
+        assert_eq!(da1_params("\x1b[?62;4"), None);
+    }
+}
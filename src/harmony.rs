@@ -0,0 +1,139 @@
+use crate::{
+    color::{ColorValue, Oklch},
+    palette::{print_palette_grid, GridOptions, LabelKind},
+};
+
+// colors in a monochromatic scheme, the base included
+const MONO_STEPS: usize = 5;
+
+// color schemes built from one base color, rotating hue or blending in OKLCH so
+// steps look evenly spaced
+#[derive(Clone, Copy, PartialEq)]
+pub enum Harmony {
+    Complementary,
+    SplitComplementary,
+    Analogous,
+    Triadic,
+    Tetradic,
+    Tints,
+    Shades,
+    Tones,
+}
+
+impl Harmony {
+    pub const ALL: [Self; 8] = [
+        Self::Complementary, Self::SplitComplementary, Self::Analogous, Self::Triadic,
+        Self::Tetradic, Self::Tints, Self::Shades, Self::Tones,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "complementary" | "complement" => Some(Self::Complementary),
+            "split" | "split-complementary" => Some(Self::SplitComplementary),
+            "analogous" => Some(Self::Analogous),
+            "triadic" => Some(Self::Triadic),
+            "tetradic" => Some(Self::Tetradic),
+            "tints" => Some(Self::Tints),
+            "shades" => Some(Self::Shades),
+            "tones" => Some(Self::Tones),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Complementary => "complementary",
+            Self::SplitComplementary => "split-complementary",
+            Self::Analogous => "analogous",
+            Self::Triadic => "triadic",
+            Self::Tetradic => "tetradic",
+            Self::Tints => "tints",
+            Self::Shades => "shades",
+            Self::Tones => "tones",
+        }
+    }
+
+    // the scheme for `base`, which always comes first
+    pub fn generate(self, base: (u8, u8, u8)) -> Vec<(u8, u8, u8)> {
+        let base_lch = Oklch::from_rgb(base.0, base.1, base.2);
+        let rotate = |offsets: &[f32]| -> Vec<(u8, u8, u8)> {
+            offsets.iter().map(|&d| Oklch { h: base_lch.h + d, ..base_lch }.to_rgb()).collect()
+        };
+        // t from 0 (base) towards 1 (the target) in even steps, stopping short of it
+        let blend = |f: &dyn Fn(f32) -> Oklch| -> Vec<(u8, u8, u8)> {
+            (0..MONO_STEPS).map(|i| f(i as f32 / MONO_STEPS as f32).to_rgb()).collect()
+        };
+
+        let mut colors = match self {
+            Self::Complementary => rotate(&[0.0, 180.0]),
+            Self::SplitComplementary => rotate(&[0.0, 150.0, 210.0]),
+            Self::Analogous => rotate(&[0.0, -30.0, 30.0]),
+            Self::Triadic => rotate(&[0.0, 120.0, 240.0]),
+            Self::Tetradic => rotate(&[0.0, 60.0, 180.0, 240.0]),
+            Self::Tints => blend(&|t| Oklch { l: base_lch.l + (1.0 - base_lch.l) * t, c: base_lch.c * (1.0 - t), ..base_lch }),
+            Self::Shades => blend(&|t| Oklch { l: base_lch.l * (1.0 - t), c: base_lch.c * (1.0 - t), ..base_lch }),
+            Self::Tones => blend(&|t| Oklch { c: base_lch.c * (1.0 - t), ..base_lch }),
+        };
+        // the base itself shouldn't drift through the round trip
+        colors[0] = base;
+        colors
+    }
+}
+
+// each harmony as a row of hex labeled swatches, or with `export` just the hex
+// values, one per line with a `#` comment naming each scheme
+pub fn print_harmonies(base: (u8, u8, u8), harmonies: &[Harmony], width: u8, height: u8, export: bool) {
+    for (i, harmony) in harmonies.iter().enumerate() {
+        let colors = harmony.generate(base);
+        if export {
+            if harmonies.len() > 1 {
+                println!("# {}", harmony.name());
+            }
+            for (r, g, b) in colors {
+                println!("#{:02x}{:02x}{:02x}", r, g, b);
+            }
+            continue;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("\x1b[2m{}\x1b[22m", harmony.name());
+        let swatches: Vec<(String, ColorValue)> =
+            colors.into_iter().map(|(r, g, b)| (String::new(), ColorValue::Rgb(r, g, b))).collect();
+        let opts = GridOptions { columns: swatches.len(), width, height, label: LabelKind::Hex };
+        print_palette_grid(&swatches, &opts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hue_gap(a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+        let d = (Oklch::from_rgb(a.0, a.1, a.2).h - Oklch::from_rgb(b.0, b.1, b.2).h).rem_euclid(360.0);
+        d.min(360.0 - d)
+    }
+
+    #[test]
+    fn hue_rotations_keep_their_angles() {
+        // a muted base stays inside sRGB at every rotation, so hues land exactly
+        let base = (150, 110, 90);
+        let complement = Harmony::Complementary.generate(base);
+        assert!((hue_gap(complement[0], complement[1]) - 180.0).abs() < 3.0);
+        let triad = Harmony::Triadic.generate(base);
+        assert_eq!(triad.len(), 3);
+        assert!((hue_gap(triad[1], triad[2]) - 120.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn monochromatic_steps_move_one_way() {
+        let base = (30, 120, 200);
+        let lightness = |c: &(u8, u8, u8)| Oklch::from_rgb(c.0, c.1, c.2).l;
+        let tints = Harmony::Tints.generate(base);
+        assert_eq!(tints.len(), MONO_STEPS);
+        assert!(tints.windows(2).all(|w| lightness(&w[1]) > lightness(&w[0])));
+        let shades = Harmony::Shades.generate(base);
+        assert!(shades.windows(2).all(|w| lightness(&w[1]) < lightness(&w[0])));
+    }
+}
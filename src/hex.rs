@@ -1,22 +1,66 @@
-use crate::terminal::terminal_size;
+use crate::{
+    ansi::{push_color, rgb_sgr, ColorDepth},
+    color::to_hex,
+    export::ExportFormat,
+    gradient::Gradient,
+    graphics::{self, cell_pixels, print_raster, Raster},
+    shade::push_gradient,
+    terminal::terminal_size,
+    view::Dither,
+};
+use std::fmt::Write as _;
 use std::io::{self, BufWriter, Write};
 
-pub fn print_hex_gradient(hex1: Vec<&str>, hex2: Vec<&str>, fit_width: bool) {
-    let r1 = u8::from_str_radix(hex1[0], 16).unwrap_or(0);
-    let g1 = u8::from_str_radix(hex1[1], 16).unwrap_or(0);
-    let b1 = u8::from_str_radix(hex1[2], 16).unwrap_or(0);
+// how a gradient strip is drawn
+pub struct StripOptions {
+    pub fit_width: bool,
+    pub depth: ColorDepth,
+    pub dither: Dither,
+    pub sample: Option<Sampling>,
+}
 
-    let r2 = u8::from_str_radix(hex2[0], 16).unwrap_or(0);
-    let g2 = u8::from_str_radix(hex2[1], 16).unwrap_or(0);
-    let b2 = u8::from_str_radix(hex2[2], 16).unwrap_or(0);
+// --sample: colors pulled out of a gradient instead of drawing it
+pub struct Sampling {
+    pub count: usize,
+    pub format: ExportFormat,
+    pub swatch: Option<u8>, // width of a block drawn before each color
+}
 
-    let dr = (r2 as i16 - r1 as i16).abs() as usize;
-    let dg = (g2 as i16 - g1 as i16).abs() as usize;
-    let db = (b2 as i16 - b1 as i16).abs() as usize;
+// `count` colors evenly spaced from one end of `gradient` to the other, one per line;
+// None for formats that aren't a list of colors
+pub fn export_samples(gradient: &Gradient, sampling: &Sampling, depth: ColorDepth) -> Option<String> {
+    let last = sampling.count.saturating_sub(1).max(1) as f32;
+    let mut out = String::new();
+    for i in 0..sampling.count {
+        let t = i as f32 / last;
+        let (r, g, b) = gradient.at(t);
+        if let Some(width) = sampling.swatch {
+            push_color(&mut out, depth, false, r, g, b);
+            let _ = write!(out, "{:width$}\x1b[0m ", "", width = width as usize);
+        }
+        let _ = match sampling.format {
+            ExportFormat::Hex => writeln!(out, "{}", to_hex((r, g, b))),
+            ExportFormat::Rgb => writeln!(out, "rgb({}, {}, {})", r, g, b),
+            ExportFormat::Json => writeln!(out, "{{\"position\": {:.4}, \"hex\": \"{}\", \"rgb\": [{}, {}, {}]}}", t, to_hex((r, g, b)), r, g, b),
+            _ => return None,
+        };
+    }
+    Some(out)
+}
 
-    let default_steps = dr.max(dg).max(db).max(1);
+// one cell per step along `gradient`; unless fit to the terminal, as many cells as it
+// takes for neighbouring cells to differ by at most one channel level
+pub fn print_hex_gradient(gradient: &Gradient, opts: &StripOptions) {
+    if let Some(sampling) = &opts.sample {
+        match export_samples(gradient, sampling, opts.depth) {
+            Some(samples) => print!("{}", samples),
+            None => eprintln!("Error: --sample can't print as {} (expected hex, rgb or json)", sampling.format.name()),
+        }
+        return;
+    }
 
-    let steps = if fit_width {
+    let default_steps = gradient.distinct_steps();
+    let steps = if opts.fit_width {
         match terminal_size() {
             Ok((w, _)) if w >= 1 => (w - 1) as usize,
             _ => default_steps,
@@ -25,35 +69,62 @@ pub fn print_hex_gradient(hex1: Vec<&str>, hex2: Vec<&str>, fit_width: bool) {
         default_steps
     };
 
-    let stdout = io::stdout();
-    let mut out = BufWriter::new(stdout.lock());
-
-    for i in 0..=steps {
-        let t = i as f32 / steps as f32;
-        let ri = (r1 as f32 + (r2 as f32 - r1 as f32) * t).round() as u8;
-        let gi = (g1 as f32 + (g2 as f32 - g1 as f32) * t).round() as u8;
-        let bi = (b1 as f32 + (b2 as f32 - b1 as f32) * t).round() as u8;
-
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", ri, gi, bi);
+    // a graphics protocol draws every pixel column along the gradient, not just one per cell
+    if let Some(protocol) = graphics::protocol() {
+        let (cell_w, cell_h) = cell_pixels();
+        let mut raster = Raster::new((steps + 1) * cell_w, cell_h);
+        let last = (raster.width - 1).max(1) as f32;
+        for x in 0..raster.width {
+            raster.fill(x, 0, 1, cell_h, gradient.at(x as f32 / last));
+        }
+        print_raster(protocol, &raster, steps + 1, 1);
+        return;
     }
 
-    let _ = writeln!(out, "\x1b[0m");
+    let colors: Vec<(u8, u8, u8)> = (0..=steps).map(|i| gradient.at(i as f32 / steps as f32)).collect();
+    let mut buf = String::new();
+    push_gradient(&mut buf, &colors, opts.depth, opts.dither);
+    buf.push_str("\x1b[0m\n");
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(buf.as_bytes());
     let _ = out.flush();
 }
 
 
-pub fn print_block_hex(hex_pairs: Vec<&str>, width: u8) {
-    let r = u8::from_str_radix(hex_pairs[0], 16).unwrap_or(0);
-    let g = u8::from_str_radix(hex_pairs[1], 16).unwrap_or(0);
-    let b = u8::from_str_radix(hex_pairs[2], 16).unwrap_or(0);
+pub fn print_block_hex((r, g, b): (u8, u8, u8), width: u8) {
+    if let Some(protocol) = graphics::protocol() {
+        graphics::print_block(protocol, (r, g, b), width.into(), 1);
+        return;
+    }
 
     let stdout = io::stdout();
     let mut out = BufWriter::new(stdout.lock());
 
-    let _ = write!(out, "\x1b[48;2;{};{};{}m", r, g, b);
+    let _ = write!(out, "{}", rgb_sgr(false, r, g, b));
     for _ in 0..width {
         let _ = write!(out, " ");
     }
     let _ = writeln!(out, "\x1b[0m");
     let _ = out.flush();
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_evenly_from_end_to_end() {
+        let gradient = Gradient::new(vec![(0, 0, 0), (255, 255, 255)]);
+        let sampling = |count, format| Sampling { count, format, swatch: None };
+        let hex = export_samples(&gradient, &sampling(3, ExportFormat::Hex), ColorDepth::TrueColor);
+        assert_eq!(hex.as_deref(), Some("#000000\n#808080\n#ffffff\n"));
+        let rgb = export_samples(&gradient, &sampling(2, ExportFormat::Rgb), ColorDepth::TrueColor);
+        assert_eq!(rgb.as_deref(), Some("rgb(0, 0, 0)\nrgb(255, 255, 255)\n"));
+        let json = export_samples(&gradient, &sampling(5, ExportFormat::Json), ColorDepth::TrueColor).unwrap();
+        assert_eq!(json.lines().nth(1), Some("{\"position\": 0.2500, \"hex\": \"#404040\", \"rgb\": [64, 64, 64]}"));
+        assert!(export_samples(&gradient, &sampling(3, ExportFormat::Kitty), ColorDepth::TrueColor).is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use std::{fs, io};
+
+use crate::inflate::zlib_decompress;
+
+// Decoding for the image formats clrblk reads: PPM (P3/P6), uncompressed BMP and PNG
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>, // RGBA, rows top to bottom
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> io::Result<u32> {
+    let b = bytes.get(at..at + 4).ok_or_else(|| invalid("file cut off"))?;
+    Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> io::Result<u32> {
+    let b = bytes.get(at..at + 4).ok_or_else(|| invalid("file cut off"))?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> io::Result<u16> {
+    let b = bytes.get(at..at + 2).ok_or_else(|| invalid("file cut off"))?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+// product of sizes read from a header, which a malformed file can make overflow
+fn checked_size(factors: &[usize]) -> io::Result<usize> {
+    factors.iter().try_fold(1usize, |n, &f| n.checked_mul(f)).ok_or_else(|| invalid("image dimensions too large"))
+}
+
+pub fn load(path: &str) -> io::Result<Image> {
+    decode(&fs::read(path)?)
+}
+
+// picks the format from the file's magic bytes
+pub fn decode(bytes: &[u8]) -> io::Result<Image> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => decode_png(bytes),
+        [b'B', b'M', ..] => decode_bmp(bytes),
+        [b'P', b'3' | b'6', ..] => decode_ppm(bytes),
+        _ => Err(invalid("unsupported image format (expected PNG, BMP or PPM)")),
+    }
+}
+
+fn decode_ppm(bytes: &[u8]) -> io::Result<Image> {
+    // header fields are whitespace separated, with `#` comments to the end of the line
+    let mut pos = 2;
+    let mut field = || -> io::Result<usize> {
+        loop {
+            match bytes.get(pos) {
+                Some(b'#') => {
+                    while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                        pos += 1;
+                    }
+                },
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                Some(_) => break,
+                None => return Err(invalid("PPM header cut off")),
+            }
+        }
+        let start = pos;
+        while bytes.get(pos).is_some_and(|b| b.is_ascii_digit()) {
+            pos += 1;
+        }
+        str::from_utf8(&bytes[start..pos]).ok().and_then(|s| s.parse().ok()).ok_or_else(|| invalid("bad PPM header"))
+    };
+    let (width, height, max) = (field()?, field()?, field()?);
+    if width == 0 || height == 0 {
+        return Err(invalid("bad PPM dimensions"));
+    }
+    if max == 0 || max > 65535 {
+        return Err(invalid("bad PPM maximum value"));
+    }
+
+    let count = checked_size(&[width, height, 3])?;
+    let samples: Vec<usize> = if bytes[1] == b'3' {
+        (0..count).map(|_| field()).collect::<io::Result<_>>()?
+    } else {
+        // one whitespace byte ends the header, then one or two bytes per sample
+        let data = bytes.get(pos + 1..).ok_or_else(|| invalid("PPM pixel data cut off"))?;
+        let size = if max < 256 { 1 } else { 2 };
+        if data.len() < checked_size(&[count, size])? {
+            return Err(invalid("PPM pixel data cut off"));
+        }
+        (0..count)
+            .map(|i| if size == 1 { data[i] as usize } else { u16::from_be_bytes([data[2 * i], data[2 * i + 1]]) as usize })
+            .collect()
+    };
+
+    let scale = |v: usize| (v.min(max) * 255 / max) as u8;
+    let pixels = samples.chunks_exact(3).map(|p| [scale(p[0]), scale(p[1]), scale(p[2]), 255]).collect();
+    Ok(Image { width, height, pixels })
+}
+
+fn decode_bmp(bytes: &[u8]) -> io::Result<Image> {
+    let offset = u32_le(bytes, 10)? as usize;
+    let width = u32_le(bytes, 18)? as i32;
+    let height = u32_le(bytes, 22)? as i32;
+    let bpp = u16_le(bytes, 28)?;
+    let compression = u32_le(bytes, 30)?;
+    // BI_RGB, or BI_BITFIELDS with the usual 32 bit BGRA layout
+    if !(bpp == 24 || bpp == 32) || !(compression == 0 || (compression == 3 && bpp == 32)) {
+        return Err(invalid("only uncompressed 24 and 32 bit BMPs are supported"));
+    }
+
+    let (width, top_down) = (width.unsigned_abs() as usize, height < 0);
+    let height = height.unsigned_abs() as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid("bad BMP dimensions"));
+    }
+    let pixel_size = bpp as usize / 8;
+    let stride = checked_size(&[width, pixel_size])?.div_ceil(4) * 4;
+    let end = offset.checked_add(checked_size(&[stride, height])?).ok_or_else(|| invalid("BMP pixel data cut off"))?;
+    let data = bytes.get(offset..end).ok_or_else(|| invalid("BMP pixel data cut off"))?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = if top_down { y } else { height - 1 - y };
+        for px in data[row * stride..].chunks_exact(pixel_size).take(width) {
+            pixels.push([px[2], px[1], px[0], 255]);
+        }
+    }
+    Ok(Image { width, height, pixels })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+// undo the per row filters in place, leaving rows without their filter bytes
+fn unfilter(data: &[u8], height: usize, stride: usize, bpp: usize) -> io::Result<Vec<u8>> {
+    if data.len() < checked_size(&[height, stride + 1])? {
+        return Err(invalid("PNG pixel data cut off"));
+    }
+    let mut out = vec![0u8; height * stride];
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let src = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, rest) = out.split_at_mut(y * stride);
+        let prev = if y > 0 { &done[(y - 1) * stride..] } else { &[][..] };
+        let row = &mut rest[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { row[x - bpp] } else { 0 };
+            let b = prev.get(x).copied().unwrap_or(0);
+            let c = if x >= bpp { prev.get(x - bpp).copied().unwrap_or(0) } else { 0 };
+            row[x] = src[x].wrapping_add(match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(invalid("bad PNG filter")),
+            });
+        }
+    }
+    Ok(out)
+}
+
+fn decode_png(bytes: &[u8]) -> io::Result<Image> {
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut idat = Vec::new();
+
+    while pos + 8 <= bytes.len() {
+        let len = u32_be(bytes, pos)? as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let body = bytes.get(pos + 8..pos + 8 + len).ok_or_else(|| invalid("PNG chunk cut off"))?;
+        match kind {
+            b"IHDR" if len >= 13 => header = Some((u32_be(body, 0)? as usize, u32_be(body, 4)? as usize, body[8], body[9], body[12])),
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2], 255]).collect(),
+            b"tRNS" => {
+                for (entry, &alpha) in palette.iter_mut().zip(body) {
+                    entry[3] = alpha;
+                }
+            },
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {},
+        }
+        pos += 12 + len; // length, type, body and CRC
+    }
+
+    let (width, height, depth, color_type, interlace) = header.ok_or_else(|| invalid("PNG has no header"))?;
+    if width == 0 || height == 0 {
+        return Err(invalid("bad PNG dimensions"));
+    }
+    if interlace != 0 {
+        return Err(invalid("interlaced PNGs are not supported"));
+    }
+    let channels = match color_type {
+        0 => 1, // gray
+        2 => 3, // RGB
+        3 => 1, // palette index
+        4 => 2, // gray and alpha
+        6 => 4, // RGBA
+        _ => return Err(invalid("bad PNG color type")),
+    };
+    // bit depths the spec allows for each color type
+    let depth_ok = match color_type {
+        0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(depth, 1 | 2 | 4 | 8),
+        _ => matches!(depth, 8 | 16),
+    };
+    if !depth_ok {
+        return Err(invalid("bad PNG bit depth for its color type"));
+    }
+    let bits = channels * depth as usize;
+    let stride = checked_size(&[width, bits])?.div_ceil(8);
+    let data = unfilter(&zlib_decompress(&idat)?, height, stride, bits.div_ceil(8))?;
+
+    // sample `i` of a row, scaled to 8 bits (16 bit samples keep their high byte)
+    let sample = |row: &[u8], i: usize| -> u8 {
+        match depth {
+            8 => row[i],
+            16 => row[2 * i],
+            _ => {
+                let per_byte = 8 / depth as usize;
+                let shift = 8 - depth as usize * (i % per_byte + 1);
+                let v = (row[i / per_byte] >> shift) & ((1 << depth) - 1);
+                if color_type == 3 { v } else { v * (255 / ((1 << depth) - 1)) }
+            },
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in data.chunks_exact(stride) {
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+            pixels.push(match color_type {
+                0 => [s(0), s(0), s(0), 255],
+                2 => [s(0), s(1), s(2), 255],
+                3 => *palette.get(s(0) as usize).ok_or_else(|| invalid("PNG palette index out of range"))?,
+                4 => [s(0), s(0), s(0), s(1)],
+                _ => [s(0), s(1), s(2), s(3)],
+            });
+        }
+    }
+    Ok(Image { width, height, pixels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_png_filters_and_palettes() {
+        // 2x5 RGB with rows filtered None, Sub, Up, Average and Paeth
+        let rgb = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 5, 8, 2, 0, 0, 0, 224,
+            209, 170, 203, 0, 0, 0, 43, 73, 68, 65, 84, 120, 218, 99, 248, 207, 192, 192, 240, 159, 129, 17, 72, 252,
+            255, 207, 192, 196, 37, 34, 175, 105, 108, 203, 124, 56, 74, 121, 33, 131, 27, 139, 229, 188, 139, 63, 127,
+            158, 2, 0, 187, 134, 12, 103, 17, 175, 131, 39, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+        ];
+        let image = decode(&rgb).unwrap();
+        assert_eq!((image.width, image.height), (2, 5));
+        let expected = [
+            [255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255], [10, 20, 30],
+            [40, 50, 60], [200, 100, 50], [25, 75, 125], [1, 2, 3], [250, 251, 252],
+        ];
+        assert_eq!(image.pixels, expected.map(|[r, g, b]| [r, g, b, 255]));
+
+        // 3x1 with 2 bit palette indices and a transparent second entry
+        let indexed = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 1, 2, 3, 0, 0, 0, 102,
+            142, 252, 39, 0, 0, 0, 9, 80, 76, 84, 69, 255, 0, 0, 0, 0, 255, 9, 9, 9, 179, 230, 219, 24, 0, 0, 0, 2, 116,
+            82, 78, 83, 255, 0, 229, 183, 48, 74, 0, 0, 0, 10, 73, 68, 65, 84, 120, 218, 99, 104, 1, 0, 0, 134, 0, 133,
+            17, 125, 183, 91, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+        ];
+        assert_eq!(decode(&indexed).unwrap().pixels, [[9, 9, 9, 255], [255, 0, 0, 255], [0, 0, 255, 0]]);
+    }
+
+    #[test]
+    fn decodes_bottom_up_bmp() {
+        // 2x2, 24 bit, rows padded to 8 bytes and stored bottom row first
+        let mut bmp = vec![0u8; 54];
+        bmp[..2].copy_from_slice(b"BM");
+        bmp[10] = 54;
+        bmp[18] = 2;
+        bmp[22] = 2;
+        bmp[28] = 24;
+        bmp.extend([255, 0, 0, 0, 255, 0, 0, 0]); // blue, green
+        bmp.extend([0, 0, 255, 255, 255, 255, 0, 0]); // red, white
+        let image = decode(&bmp).unwrap();
+        assert_eq!(image.pixels, [[255, 0, 0, 255], [255, 255, 255, 255], [0, 0, 255, 255], [0, 255, 0, 255]]);
+    }
+
+    #[test]
+    fn decodes_ascii_and_binary_ppm() {
+        let ascii = decode(b"P3\n# a comment\n2 1\n15\n15 0 0  0 15 15\n").unwrap();
+        assert_eq!(ascii.pixels, [[255, 0, 0, 255], [0, 255, 255, 255]]);
+
+        let binary = decode(b"P6 1 1 255\n\x10\x20\x30").unwrap();
+        assert_eq!(binary.pixels, [[16, 32, 48, 255]]);
+        assert!(decode(b"P6 2 2 255\n\x10").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_images() {
+        // header ending right at the end of the file, no pixels, and sizes that overflow
+        assert!(decode(b"P6 1 1 255").is_err());
+        assert!(decode(b"P3 0 1 255\n").is_err());
+        assert!(decode(format!("P6 {} {} 255\n", usize::MAX, usize::MAX).as_bytes()).is_err());
+
+        let mut bmp = vec![0u8; 54];
+        bmp[..2].copy_from_slice(b"BM");
+        bmp[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        bmp[18..22].copy_from_slice(&i32::MAX.to_le_bytes());
+        bmp[22..26].copy_from_slice(&i32::MIN.to_le_bytes());
+        bmp[28] = 32;
+        assert!(decode(&bmp).is_err());
+        bmp[10..14].copy_from_slice(&54u32.to_le_bytes());
+        bmp[18..22].copy_from_slice(&0i32.to_le_bytes());
+        bmp[22..26].copy_from_slice(&1i32.to_le_bytes());
+        assert!(decode(&bmp).is_err());
+
+        // a Nx1 image with the given bit depth and color type, and 32 zero bytes of
+        // pixel data in a stored deflate block (CRCs aren't checked)
+        let png = |width: u8, depth: u8, color_type: u8| {
+            let mut png = vec![137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13];
+            png.extend(b"IHDR");
+            png.extend([0, 0, 0, width, 0, 0, 0, 1, depth, color_type, 0, 0, 0, 0, 0, 0, 0]);
+            png.extend([0, 0, 0, 39]);
+            png.extend(b"IDAT");
+            png.extend([0x78, 0x01, 1, 32, 0, !32, 0xff]);
+            png.extend([0; 32 + 4 + 4]); // data, adler32 and CRC
+            png
+        };
+        assert!(decode(&png(1, 8, 6)).is_ok());
+        assert!(decode(&png(0, 8, 2)).is_err());
+        for (depth, color_type) in [(0, 0), (32, 6), (16, 3), (4, 2), (3, 0), (1, 6)] {
+            assert!(decode(&png(1, depth, color_type)).is_err(), "depth {} color type {}", depth, color_type);
+        }
+    }
+}
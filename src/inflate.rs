@@ -0,0 +1,248 @@
+use std::io;
+
+// DEFLATE (RFC 1951) decoding for PNG's zlib streams, after zlib's own `puff.c`
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// order code length code lengths are stored in
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    // `n` bits, least significant first
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("deflate stream ended early"))?;
+            self.buf |= (byte as u32) << self.count;
+            self.pos += 1;
+            self.count += 8;
+        }
+        let value = self.buf & ((1u64 << n) - 1) as u32;
+        self.buf = if n == 32 { 0 } else { self.buf >> n };
+        self.count -= n;
+        Ok(value)
+    }
+
+    // drop to the next byte boundary, for stored blocks
+    fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+}
+
+// canonical Huffman code: how many codes of each length and the symbols in code order
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    // one bit at a time; codes are stored most significant bit first
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad huffman code"))
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let clen_count = reader.bits(4)? as usize + 4;
+
+    let mut clen_lengths = [0u8; 19];
+    for &i in &CLEN_ORDER[..clen_count] {
+        clen_lengths[i] = reader.bits(3)? as u8;
+    }
+    let clen = Huffman::new(&clen_lengths);
+
+    let mut lengths = vec![0u8; literals + distances];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = clen.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths[..i].last().ok_or_else(|| invalid("repeat with no previous length"))?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        let end = i + repeat as usize;
+        if end > lengths.len() {
+            return Err(invalid("too many code lengths"));
+        }
+        lengths[i..end].fill(value);
+        i = end;
+    }
+
+    Ok((Huffman::new(&lengths[..literals]), Huffman::new(&lengths[literals..])))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err(invalid("bad length symbol"));
+                }
+                let length = LENGTH_BASE[i] as usize + reader.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = dist.decode(reader)? as usize;
+                if d >= DIST_BASE.len() {
+                    return Err(invalid("bad distance symbol"));
+                }
+                let distance = DIST_BASE[d] as usize + reader.bits(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(invalid("distance reaches before the start"));
+                }
+                // byte by byte, since the copy may overlap what it's writing
+                let start = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            },
+        }
+    }
+}
+
+// raw DEFLATE data
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut reader = BitReader { data, pos: 0, buf: 0, count: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = data.get(reader.pos..reader.pos + 4).ok_or_else(|| invalid("stored block cut off"))?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err(invalid("stored block length mismatch"));
+                }
+                let start = reader.pos + 4;
+                out.extend_from_slice(data.get(start..start + len).ok_or_else(|| invalid("stored block cut off"))?);
+                reader.pos = start + len;
+            },
+            1 => {
+                let (lit, dist) = fixed_codes();
+                inflate_block(&mut reader, &mut out, &lit, &dist)?;
+            },
+            2 => {
+                let (lit, dist) = dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &lit, &dist)?;
+            },
+            _ => return Err(invalid("bad block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+// a zlib stream: two header bytes, DEFLATE data and a checksum (not verified)
+pub fn zlib_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    match data {
+        [cmf, flg, rest @ ..] if cmf & 0x0f == 8 && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31) && flg & 0x20 == 0 => {
+            inflate(rest)
+        },
+        _ => Err(invalid("not a zlib stream")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inflates_stored_fixed_and_dynamic_blocks() {
+        let stored = [120, 1, 1, 6, 0, 249, 255, 115, 116, 111, 114, 101, 100, 9, 60, 2, 146];
+        assert_eq!(zlib_decompress(&stored).unwrap(), b"stored");
+
+        // back references reaching over earlier copies of the sentence
+        let fixed = [
+            120, 1, 43, 201, 72, 85, 40, 44, 205, 76, 206, 86, 72, 42, 202, 47, 207, 83, 72, 203, 175, 80, 200, 42, 205,
+            45, 40, 86, 200, 47, 75, 45, 82, 40, 1, 74, 231, 36, 86, 85, 42, 164, 228, 167, 235, 128, 121, 104, 138, 21,
+            177, 9, 210, 221, 4, 0, 227, 121, 71, 44,
+        ];
+        assert_eq!(zlib_decompress(&fixed).unwrap(), b"the quick brown fox jumps over the lazy dog, the quick brown fox! ".repeat(3));
+
+        let dynamic = [
+            120, 218, 37, 205, 75, 18, 194, 32, 16, 69, 209, 173, 60, 231, 22, 235, 112, 104, 149, 110, 0, 164, 9, 40,
+            129, 64, 248, 175, 94, 82, 140, 251, 245, 185, 79, 254, 249, 97, 239, 16, 190, 161, 154, 164, 161, 76, 33,
+            72, 63, 200, 193, 154, 144, 125, 196, 55, 111, 39, 195, 195, 87, 20, 106, 198, 109, 182, 35, 100, 51, 223,
+            36, 87, 9, 131, 68, 228, 231, 28, 237, 199, 13, 175, 67, 27, 215, 224, 21, 132, 189, 224, 144, 121, 76, 227,
+            62, 175, 114, 163, 43, 83, 124, 101, 120, 107, 90, 153, 25, 157, 222, 236, 14, 30, 229, 50, 22, 109, 59,
+            251, 3, 171, 207, 55, 156,
+        ];
+        let text = "Pack my box with five dozen liquor jugs. How vexingly quick daft zebras jump! \
+                    Sphinx of black quartz, judge my vow. The five boxing wizards jump quickly.";
+        assert_eq!(zlib_decompress(&dynamic).unwrap(), text.as_bytes());
+    }
+
+    #[test]
+    fn rejects_corrupt_streams() {
+        assert!(zlib_decompress(&[0x78]).is_err());
+        assert!(zlib_decompress(&[120, 1, 1, 6, 0, 0, 0, 115]).is_err());
+        assert!(inflate(&[0b111]).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::HashSet,
+    fs,
+    fmt::Write as _,
+    io::{self, BufWriter, Read, Write},
+    ops::Range,
+    sync::OnceLock,
+};
+
+use crate::{
+    color::{parse_color, ColorValue},
+    palette::{push_background, push_contrast_text},
+};
+
+// a color found in some text, with where it came from
+pub struct FoundColor {
+    pub token: String,
+    pub color: ColorValue,
+    pub source: String,
+    pub line: usize,
+}
+
+const CSS_FUNCTIONS: [&str; 4] = ["rgb", "rgba", "hsl", "hsla"];
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '#'
+}
+
+// byte ranges of the colors in a line of source: `#` hex codes, `rgb()`/`hsl()`
+// calls and ANSI names. Dashes join words so `white-space` isn't taken for `white`
+pub fn find_colors(line: &str) -> Vec<(Range<usize>, ColorValue)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while let Some(c) = line[i..].chars().next() {
+        if !is_word_char(c) {
+            i += c.len_utf8();
+            continue;
+        }
+
+        // '#' only starts a word, so `a#fff` yields `a` and `#fff`
+        let start = i;
+        i += c.len_utf8();
+        while let Some(c) = line[i..].chars().next() && is_word_char(c) && c != '#' {
+            i += c.len_utf8();
+        }
+        let word = &line[start..i];
+
+        if line[i..].starts_with('(') && CSS_FUNCTIONS.contains(&word.to_lowercase().as_str()) {
+            if let Some(close) = line[i..].find(')')
+                && let Some(color) = parse_color(&line[start..i + close + 1])
+            {
+                i += close + 1;
+                found.push((start..i, color));
+            }
+            continue;
+        }
+
+        let is_candidate = word.starts_with('#') || word.chars().all(|c| c.is_ascii_alphabetic() || c == '_');
+        if let Some(color) = parse_color(word).filter(|_| is_candidate) {
+            found.push((start..i, color));
+        }
+    }
+    found
+}
+
+// colors in `text`: `#` hex codes, CSS functions and ANSI names anywhere, plus
+// bare ANSI codes and hex strings when they make up a whole line (as in a piped list)
+pub fn extract_colors(text: &str, source: &str) -> Vec<FoundColor> {
+    let mut found = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(color) = parse_color(trimmed) {
+            found.push(FoundColor { token: trimmed.to_string(), color, source: source.to_string(), line: i + 1 });
+            continue;
+        }
+
+        for (range, color) in find_colors(line) {
+            found.push(FoundColor { token: line[range].to_string(), color, source: source.to_string(), line: i + 1 });
+        }
+    }
+    found
+}
+
+// colors in a terminal theme (kitty, alacritty, Xresources and the like) named by the key
+// they're assigned to, e.g. `color4`, `background` or `bright_blue` for alacritty's
+// `blue` under `[colors.bright]`
+pub fn import_palette(text: &str) -> Vec<(String, ColorValue)> {
+    let mut palette = Vec::new();
+    let mut section = "";
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+            section = header.rsplit('.').next().unwrap_or(header);
+            continue;
+        }
+        if trimmed.starts_with(['#', '!', ';']) && find_colors(trimmed).first().is_none_or(|(r, _)| r.start != 0) {
+            continue; // a comment
+        }
+
+        for (range, color) in find_colors(line) {
+            // keys like alacritty's `blue = ...` are names too, so only a line holding
+            // nothing but a name counts it as a color
+            let is_name = line[range.clone()].chars().all(|c| c.is_ascii_alphabetic() || c == '_');
+            if is_name && trimmed != &line[range.clone()] {
+                continue;
+            }
+            let key = line[..range.start]
+                .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '=' | ':' | '"' | '\''))
+                .rsplit(|c: char| c.is_whitespace() || c == '.' || c == '*')
+                .next()
+                .unwrap_or_default();
+            let name = match (key, section) {
+                ("", _) => line[range].to_string(),
+                (key, "bright") => format!("bright_{}", key),
+                (key, _) => key.to_string(),
+            };
+            palette.push((name, color));
+        }
+    }
+    palette
+}
+
+// stdin as first read, so output drawn twice (--compare) sees it both times
+static STDIN: OnceLock<String> = OnceLock::new();
+
+// `-` reads stdin, anything else is a path
+pub fn read_source(path: &str) -> io::Result<(String, String)> {
+    let mut bytes = Vec::new();
+    if path == "-" {
+        if let Some(text) = STDIN.get() {
+            return Ok(("stdin".to_string(), text.clone()));
+        }
+        io::stdin().read_to_end(&mut bytes)?;
+        let text = STDIN.get_or_init(|| String::from_utf8_lossy(&bytes).into_owned());
+        Ok(("stdin".to_string(), text.clone()))
+    } else {
+        bytes = fs::read(path)?;
+        Ok((path.to_string(), String::from_utf8_lossy(&bytes).into_owned()))
+    }
+}
+
+// keep the first occurrence of every distinct color
+pub fn dedup(colors: Vec<FoundColor>) -> Vec<FoundColor> {
+    let mut seen = HashSet::new();
+    colors.into_iter().filter(|c| seen.insert(c.color)).collect()
+}
+
+// one swatch per color, labeled with the token as written and where it was found
+pub fn print_found(colors: &[FoundColor], width: u8) {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let space_block = " ".repeat(width.into());
+    let token_width = colors.iter().map(|c| c.token.chars().count()).max().unwrap_or(0);
+
+    for found in colors {
+        let mut swatch = String::new();
+        push_background(&mut swatch, found.color);
+        let _ = write!(out, "{}", swatch);
+        let _ = writeln!(
+            out,
+            "{}\x1b[0m {:<width$}  \x1b[2m{}:{}\x1b[22m",
+            space_block, found.token, found.source, found.line, width = token_width
+        );
+    }
+
+    let _ = out.flush();
+}
+
+// `text` reprinted with line numbers and every color token drawn on its own color;
+// returns the colors it highlighted
+pub fn print_highlighted(text: &str, source: &str) -> Vec<FoundColor> {
+    let gutter = text.lines().count().max(1).to_string().len();
+    let mut buf = String::new();
+    let mut found = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let _ = write!(buf, "\x1b[2m{:>width$} │\x1b[22m ", i + 1, width = gutter);
+        let mut last = 0;
+        for (range, color) in find_colors(line) {
+            buf.push_str(&line[last..range.start]);
+            push_background(&mut buf, color);
+            push_contrast_text(&mut buf, color);
+            buf.push_str(&line[range.clone()]);
+            buf.push_str("\x1b[0m");
+            last = range.end;
+            found.push(FoundColor { token: line[range].to_string(), color, source: source.to_string(), line: i + 1 });
+        }
+        buf.push_str(&line[last..]);
+        buf.push('\n');
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(buf.as_bytes());
+    let _ = out.flush();
+    found
+}
+
+// every distinct color with how often it appears and where it was first seen
+pub fn print_summary(colors: &[FoundColor], width: u8) {
+    let mut distinct: Vec<(&FoundColor, usize)> = Vec::new();
+    for found in colors {
+        match distinct.iter_mut().find(|(first, _)| first.color == found.color) {
+            Some((_, count)) => *count += 1,
+            None => distinct.push((found, 1)),
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let space_block = " ".repeat(width.into());
+    let _ = writeln!(out, "\n{} distinct color{}", distinct.len(), if distinct.len() == 1 { "" } else { "s" });
+    let _ = writeln!(out, "\x1b[2m{:w$} {:<8} {:>5}  first seen\x1b[22m", "", "hex", "count", w = width as usize);
+
+    for (found, count) in distinct {
+        let (r, g, b) = found.color.rgb();
+        let mut swatch = String::new();
+        push_background(&mut swatch, found.color);
+        let _ = writeln!(
+            out,
+            "{}{}\x1b[0m #{:02x}{:02x}{:02x} {:>5}  {}:{} {}",
+            swatch, space_block, r, g, b, count, found.source, found.line, found.token
+        );
+    }
+
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_tokens_in_text_and_whole_line_values() {
+        let text = "a { color: #ff8800; border: 1px solid red }\n12\nff00aa\nwidth: 12px\n";
+        let found: Vec<_> = extract_colors(text, "t").into_iter().map(|c| (c.token, c.line)).collect();
+        let expected = [("#ff8800", 1), ("red", 1), ("12", 2), ("ff00aa", 3)];
+        assert_eq!(found, expected.map(|(t, l)| (t.to_string(), l)));
+    }
+
+    #[test]
+    fn finds_css_functions_and_skips_dashed_words() {
+        let line = "  white-space: nowrap; color: rgb(0, 128, 255); border: 1px solid #f00;";
+        let tokens: Vec<_> = find_colors(line).into_iter().map(|(r, _)| &line[r]).collect();
+        assert_eq!(tokens, ["rgb(0, 128, 255)", "#f00"]);
+    }
+
+    #[test]
+    fn imports_theme_keys() {
+        let kitty = "# theme\nbackground #101010\ncolor4 #0000ee\n";
+        let xresources = "*.color12: #5c5cff\n";
+        let alacritty = "[colors.normal]\nblue = \"#0000ee\"\n[colors.bright]\nblue = \"#5c5cff\"\n";
+        let names = |text: &str| -> Vec<String> { import_palette(text).into_iter().map(|(n, _)| n).collect() };
+        assert_eq!(names(kitty), ["background", "color4"]);
+        assert_eq!(names(xresources), ["color12"]);
+        assert_eq!(names(alacritty), ["blue", "bright_blue"]);
+        assert_eq!(names("#ff0000\nred\n"), ["#ff0000", "red"]);
+    }
+
+    #[test]
+    fn dedup_keeps_first_occurrence() {
+        let found = dedup(extract_colors("#ff0000\nred\n#FF0000\n", "t"));
+        let lines: Vec<_> = found.iter().map(|c| c.line).collect();
+        assert_eq!(lines, [1, 2]);
+    }
+}
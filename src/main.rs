@@ -1,20 +1,44 @@
 #![feature(portable_simd)]
 
+mod analyze;
+mod animation;
 mod ansi;
+mod bench;
+mod cast;
 mod cli;
+mod color;
+mod colorize;
+mod css;
+mod easing;
+mod export;
+mod extract;
+mod gradient;
+mod graphics;
+mod harmony;
 mod hex;
+mod image;
+mod inflate;
+mod input;
+mod palette;
 mod rainbow;
+mod ramp;
 mod validate;
+mod view;
 mod terminal;
+mod theme;
 mod rng;
+mod simulate;
+mod shade;
 
-use cli::{Args, many, single, parse_args, print_help};
-use rainbow::{print_grayscale, print_rainbow, crazyfn};
+use cli::{Args, many, single, parse_args, print_help, resolve_random};
+use gradient::GradientShape;
+use hex::{Sampling, StripOptions};
+use rainbow::{print_grayscale, print_rainbow, crazyfn, CrazyOptions, RainbowOptions};
 
 
 
 fn main() {
-    let args: Args = parse_args();
+    let mut args: Args = parse_args();
     if args.error {
         return;
     }
@@ -27,21 +51,311 @@ fn main() {
         println!("clrblk version {}", version);
         return;
     }
-    if args.rainbow {
-        print_rainbow();
+    let seed = args.seed.unwrap_or_else(rng::clock_seed);
+    if resolve_random(&mut args.values, &mut rng::SimpleRng::with_seed(seed)) {
+        eprintln!("seed: {}", seed);
+    }
+
+    let crazy_opts = CrazyOptions {
+        fps: args.fps,
+        fade_speed: args.fade_speed,
+        goal: args.goal,
+        animation: args.animation,
+        seed,
+        scalar: args.scalar,
+        stats: args.stats,
+        record: args.record.clone(),
+        threads: args.threads,
+    };
+
+    if let Some(protocol) = args.graphics.resolve() {
+        graphics::install(protocol);
+    }
+    if let Some(deficiency) = args.simulate {
+        simulate::install(simulate::Simulation::new(deficiency, args.severity));
+    }
+
+    match (args.compare, args.simulate) {
+        (false, _) => render(&args, &crazy_opts),
+        (true, None) => eprintln!("Error: --compare needs --simulate"),
+        (true, Some(_)) if args.crazy || args.bench || args.replay.is_some() || args.animate => {
+            eprintln!("Error: --compare only works for still output");
+        },
+        (true, Some(deficiency)) => {
+            // the same output twice, as drawn and as seen with the deficiency
+            for active in [false, true] {
+                simulate::set_active(active);
+                if active {
+                    println!("\x1b[2m{} ({:.0}%)\x1b[22m", deficiency.name(), args.severity.clamp(0.0, 1.0) * 100.0);
+                } else {
+                    println!("\x1b[2moriginal\x1b[22m");
+                }
+                render(&args, &crazy_opts);
+            }
+        },
+    }
+}
+
+// parse every value as a color, reporting the first that isn't one
+fn parse_values(values: &[String]) -> Option<Vec<color::ColorValue>> {
+    let mut colors = Vec::new();
+    for value in values {
+        match color::parse_color(value) {
+            Some(c) => colors.push(c),
+            None => {
+                eprintln!("Error: Invalid color `{}`", value);
+                return None;
+            }
+        }
+    }
+    Some(colors)
+}
+
+// how gradients are drawn, or sampled with --sample
+const SAMPLE_NEEDS_GRADIENT: &str = "Error: --sample needs a gradient (two or more colors, a CSS gradient or --palette)";
+
+fn strip_options(args: &Args) -> StripOptions {
+    let sample = args.sample.map(|count| Sampling {
+        count,
+        format: args.format.unwrap_or(export::ExportFormat::Hex),
+        swatch: args.swatches.then_some(args.width),
+    });
+    StripOptions { fit_width: args.fit, depth: args.depth, dither: args.dither, sample }
+}
+
+// --file paths, or stdin if there are none
+fn input_files(args: &Args) -> Vec<String> {
+    if args.files.is_empty() { vec!["-".to_string()] } else { args.files.clone() }
+}
+
+fn render(args: &Args, crazy_opts: &CrazyOptions) {
+    if let Some(path) = &args.replay {
+        if let Err(e) = cast::replay(path) {
+            eprintln!("Error: Could not replay `{}`: {}", path, e);
+        }
+    } else if args.bench {
+        let (w, h) = args.size.unwrap_or_else(|| terminal::terminal_size().unwrap_or((200, 60)));
+        let _ = bench::benchmark(crazy_opts, w, h, args.frames);
+    } else if args.analyze {
+        let palette: Vec<(String, color::ColorValue)> = if args.files.is_empty() {
+            let Some(colors) = parse_values(&args.values) else {
+                return;
+            };
+            args.values.iter().cloned().zip(colors).collect()
+        } else {
+            let mut palette = Vec::new();
+            for path in &args.files {
+                match input::read_source(path) {
+                    Ok((_, text)) => palette.extend(input::import_palette(&text)),
+                    Err(e) => eprintln!("Error: Could not read `{}`: {}", path, e),
+                }
+            }
+            palette
+        };
+        if palette.len() < 2 {
+            eprintln!("Error: --analyze needs at least two colors");
+            return;
+        }
+        let palette: Vec<(String, (u8, u8, u8))> = palette.into_iter().map(|(name, c)| (name, c.rgb())).collect();
+        analyze::print_report(&palette, args.min_distance);
+    } else if args.theme {
+        let Some(colors) = parse_values(&args.values) else {
+            return;
+        };
+        let (background, accent) = match colors.as_slice() {
+            [background] => (background.rgb(), None),
+            [background, accent] => (background.rgb(), Some(accent.rgb())),
+            _ => {
+                eprintln!("Error: --theme takes a background color and an optional accent");
+                return;
+            }
+        };
+        let generated = theme::Theme::generate(background, accent, args.contrast);
+        let format = args.format.unwrap_or(export::ExportFormat::Kitty);
+        if !args.export {
+            generated.preview(args.width);
+        } else if let Some(config) = generated.export(format) {
+            print!("{}", config);
+        } else {
+            eprintln!("Error: --theme can't export as {} (expected kitty, alacritty or xresources)", format.name());
+        }
+    } else if let Some(path) = &args.view {
+        match image::load(path) {
+            Ok(picture) => view::print_image(&picture, args.depth, args.dither),
+            Err(e) => eprintln!("Error: Could not read `{}`: {}", path, e),
+        }
+    } else if let Some(path) = &args.extract {
+        let picture = match image::load(path) {
+            Ok(picture) => picture,
+            Err(e) => {
+                eprintln!("Error: Could not read `{}`: {}", path, e);
+                return;
+            }
+        };
+        let colors = extract::dominant_colors(&picture, args.colors, args.seed.unwrap_or(0));
+        if colors.is_empty() {
+            eprintln!("Error: `{}` has no opaque pixels", path);
+        } else if !args.export {
+            println!("\x1b[2m{} ({}×{})\x1b[22m", path, picture.width, picture.height);
+            extract::print_swatches(&colors, args.width);
+        } else if let Some(palette) = extract::export(&colors, args.format, &args.name) {
+            print!("{}", palette);
+        } else if let Some(format) = args.format {
+            eprintln!("Error: --extract can't export as {} (expected css or json)", format.name());
+        }
+    } else if args.ramp {
+        let base = match args.values.as_slice() {
+            [value] => color::parse_color(value),
+            _ => {
+                eprintln!("Error: --ramp takes exactly one color");
+                return;
+            }
+        };
+        let Some(base) = base else {
+            eprintln!("Error: Invalid color `{}`", args.values[0]);
+            return;
+        };
+        let generated = ramp::Ramp::generate(base.rgb(), args.steps);
+        let format = args.format.unwrap_or(export::ExportFormat::Css);
+        if !args.export {
+            generated.preview(args.width);
+        } else if let Some(vars) = generated.export(format, &args.name) {
+            print!("{}", vars);
+        } else {
+            eprintln!("Error: --ramp can't export as {} (expected css or json)", format.name());
+        }
+    } else if !args.harmonies.is_empty() {
+        let base = match args.values.as_slice() {
+            [value] => color::parse_color(value),
+            _ => {
+                eprintln!("Error: --harmony takes exactly one base color");
+                return;
+            }
+        };
+        match base {
+            Some(base) => harmony::print_harmonies(base.rgb(), &args.harmonies, args.width, args.cell_height, args.export),
+            None => eprintln!("Error: Invalid color `{}`", args.values[0]),
+        }
+    } else if args.highlight {
+        let files = input_files(args);
+        let mut found = Vec::new();
+        for path in &files {
+            match input::read_source(path) {
+                Ok((source, text)) => {
+                    if files.len() > 1 {
+                        println!("\x1b[1m{}\x1b[22m", source);
+                    }
+                    found.extend(input::print_highlighted(&text, &source));
+                },
+                Err(e) => eprintln!("Error: Could not read `{}`: {}", path, e),
+            }
+        }
+        input::print_summary(&found, args.width);
+    } else if args.text {
+        let gradient = if args.values.is_empty() {
+            args.palette.clone().unwrap_or_else(gradient::Gradient::rainbow)
+        } else {
+            let Some(stops) = parse_values(&args.values) else {
+                return;
+            };
+            gradient::Gradient::new(stops.iter().map(|c| c.rgb()).collect())
+        };
+        let mut text = String::new();
+        for path in &input_files(args) {
+            match input::read_source(path) {
+                Ok((_, contents)) => text.push_str(&contents),
+                Err(e) => eprintln!("Error: Could not read `{}`: {}", path, e),
+            }
+        }
+        let text_opts = colorize::TextOptions {
+            gradient: GradientShape { easing: args.easing, hint: args.hint }.apply(gradient),
+            direction: args.direction,
+            animate: args.animate,
+            duration: args.duration,
+            fps: args.fps,
+            record: args.record.clone(),
+        };
+        if let Err(e) = colorize::colorize(&text, &text_opts) {
+            eprintln!("Error: {}", e);
+        }
+    } else if !args.files.is_empty() {
+        let mut found = Vec::new();
+        for path in &args.files {
+            match input::read_source(path) {
+                Ok((source, text)) => found.extend(input::extract_colors(&text, &source)),
+                Err(e) => eprintln!("Error: Could not read `{}`: {}", path, e),
+            }
+        }
+        if args.unique {
+            found = input::dedup(found);
+        }
+        if found.is_empty() {
+            eprintln!("Error: No colors found");
+        }
+        input::print_found(&found, args.width);
+    } else if args.chart {
+        palette::print_xterm_chart(args.width, args.numbered, args.axes);
+    } else if args.rainbow {
+        let rainbow_opts = RainbowOptions {
+            path: args.hue_path,
+            saturation: args.saturation,
+            lightness: args.lightness,
+            cycles: args.cycles,
+            reverse: args.reverse,
+        };
+        print_rainbow(&rainbow_opts, &strip_options(args));
     } else if args.grayscale {
-        print_grayscale();
+        print_grayscale(args.gray_steps, &strip_options(args));
     } else if args.crazy {
-        let _ = crazyfn();
+        if let Err(e) = crazyfn(crazy_opts) {
+            eprintln!("Error: {}", e);
+        }
+    } else if let [value] = args.values.as_slice() && css::is_gradient(value) {
+        match css::CssGradient::parse(value) {
+            Ok(mut css) => {
+                css.gradient = GradientShape { easing: args.easing, hint: args.hint }.apply(css.gradient);
+                if args.fill {
+                    css::print_fill(&css, args.depth, args.dither);
+                } else {
+                    hex::print_hex_gradient(&css.gradient, &strip_options(args));
+                }
+            },
+            Err(e) => e.print(value),
+        }
+    } else if args.values.len() > 2 || (args.grid && !args.values.is_empty()) {
+        let Some(parsed) = parse_values(&args.values) else {
+            return;
+        };
+        // with --sample the colors are stops of one gradient instead of grid cells
+        if args.sample.is_some() {
+            if parsed.len() < 2 {
+                eprintln!("{}", SAMPLE_NEEDS_GRADIENT);
+                return;
+            }
+            let gradient = gradient::Gradient::new(parsed.iter().map(|c| c.rgb()).collect());
+            hex::print_hex_gradient(&GradientShape { easing: args.easing, hint: args.hint }.apply(gradient), &strip_options(args));
+            return;
+        }
+        let colors: Vec<(String, color::ColorValue)> = args.values.iter().cloned().zip(parsed).collect();
+        let grid_opts = palette::GridOptions {
+            columns: args.columns,
+            width: args.width,
+            height: args.cell_height,
+            label: args.label,
+        };
+        palette::print_palette_grid(&colors, &grid_opts);
     } else if args.values.len() == 2 {
-        many(&args.values, args.width, args.inline, args.numbered, args.fit);
+        let shape = GradientShape { easing: args.easing, hint: args.hint };
+        many(&args.values, args.width, args.inline, args.numbered, &shape, &strip_options(args));
+    } else if let Some(gradient) = args.palette.clone().filter(|_| args.values.is_empty()) {
+        let gradient = GradientShape { easing: args.easing, hint: args.hint }.apply(gradient);
+        hex::print_hex_gradient(&gradient, &strip_options(args));
+    } else if args.values.len() == 1 && args.sample.is_some() {
+        eprintln!("{}", SAMPLE_NEEDS_GRADIENT);
     } else if args.values.len() == 1 {
         single(&args.values, args.width, args.numbered);
-    } else if args.values.is_empty() {
-        eprintln!("Error: No arguments provided");
-        print_help();
     } else {
-        eprintln!("Error: Too many arguments (max 2 colors)");
+        eprintln!("Error: No arguments provided");
         print_help();
     }
 }
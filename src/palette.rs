@@ -0,0 +1,262 @@
+use std::{
+    fmt::Write as _,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    ansi::{ansi_name, ansi_sgr, rgb_sgr, rgb_to_ansi256},
+    color::{relative_luminance, ColorValue},
+    graphics::{self, cell_pixels, print_raster, Raster},
+    simulate,
+    terminal::terminal_size,
+};
+
+// luminance where black and white text have the same contrast ratio
+const CONTRAST_SPLIT: f32 = 0.179;
+
+// text drawn inside each swatch
+#[derive(Clone, Copy, PartialEq)]
+pub enum LabelKind {
+    None,
+    Hex,
+    Index,
+    Name,
+}
+
+impl LabelKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none"  => Some(Self::None),
+            "hex"   => Some(Self::Hex),
+            "index" => Some(Self::Index),
+            "name"  => Some(Self::Name),
+            _ => None,
+        }
+    }
+
+    fn text(self, token: &str, color: ColorValue) -> String {
+        match (self, color) {
+            (Self::None, _) => String::new(),
+            (Self::Hex, _) => {
+                let (r, g, b) = color.rgb();
+                format!("#{:02x}{:02x}{:02x}", r, g, b)
+            },
+            (Self::Index, ColorValue::Ansi(code)) => code.to_string(),
+            // hex values have no index, so show the nearest one
+            (Self::Index, ColorValue::Rgb(r, g, b)) => format!("~{}", rgb_to_ansi256(r, g, b)),
+            (Self::Name, ColorValue::Ansi(code)) => ansi_name(code).unwrap_or(token).to_string(),
+            (Self::Name, ColorValue::Rgb(..)) => token.to_lowercase(),
+        }
+    }
+}
+
+pub struct GridOptions {
+    pub columns: usize,   // 0 fits as many as the terminal allows
+    pub width: u8,
+    pub height: u8,
+    pub label: LabelKind,
+}
+
+pub fn push_background(buf: &mut String, color: ColorValue) {
+    buf.push_str(&match color {
+        ColorValue::Ansi(code) => ansi_sgr(false, code),
+        ColorValue::Rgb(r, g, b) => rgb_sgr(false, r, g, b),
+    });
+}
+
+// black or white, whichever reads better on `color`; uses palette colors 16 and 231
+// rather than 0 and 15 so themes can't remap them
+pub fn push_contrast_text(buf: &mut String, color: ColorValue) {
+    let (r, g, b) = simulate::apply(color.rgb());
+    let dark_text = relative_luminance(r, g, b) > CONTRAST_SPLIT;
+    buf.push_str(&match color {
+        ColorValue::Ansi(_) => ansi_sgr(true, if dark_text { 16 } else { 231 }),
+        ColorValue::Rgb(..) if dark_text => rgb_sgr(true, 0, 0, 0),
+        ColorValue::Rgb(..) => rgb_sgr(true, 255, 255, 255),
+    });
+}
+
+// columns of `cell_width` (plus a one space gap) that fit in the terminal
+fn fit_columns(cell_width: usize) -> usize {
+    let term_width = terminal_size().map(|(w, _)| w as usize).unwrap_or(80);
+    ((term_width + 1) / (cell_width + 1)).max(1)
+}
+
+// swatches laid out left to right, wrapping into rows; cells widen to fit their labels
+pub fn print_palette_grid(colors: &[(String, ColorValue)], opts: &GridOptions) {
+    let labels: Vec<String> = colors.iter().map(|(token, color)| opts.label.text(token, *color)).collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let cell_width = if label_width > 0 { (opts.width as usize).max(label_width + 2) } else { opts.width as usize };
+    let cell_height = opts.height.max(1) as usize;
+    let columns = if opts.columns == 0 { fit_columns(cell_width) } else { opts.columns };
+    let label_line = (cell_height - 1) / 2;
+
+    // labels need text cells, so only bare swatches are drawn as images
+    if let Some(protocol) = graphics::protocol()
+        && label_width == 0
+    {
+        let (px_w, px_h) = cell_pixels();
+        for row in colors.chunks(columns) {
+            let cells = row.len() * (cell_width + 1) - 1;
+            let mut raster = Raster::new(cells * px_w, cell_height * px_h);
+            for (i, (_, color)) in row.iter().enumerate() {
+                raster.fill(i * (cell_width + 1) * px_w, 0, cell_width * px_w, raster.height, color.rgb());
+            }
+            print_raster(protocol, &raster, cells, cell_height);
+        }
+        return;
+    }
+
+    let mut buf = String::new();
+    for (row, row_labels) in colors.chunks(columns).zip(labels.chunks(columns)) {
+        for line in 0..cell_height {
+            for (i, ((_, color), label)) in row.iter().zip(row_labels).enumerate() {
+                if i > 0 {
+                    buf.push(' ');
+                }
+                push_background(&mut buf, *color);
+                if line == label_line && !label.is_empty() {
+                    push_contrast_text(&mut buf, *color);
+                    let _ = write!(buf, "{:^width$}", label, width = cell_width);
+                } else {
+                    buf.push_str(&" ".repeat(cell_width));
+                }
+                buf.push_str("\x1b[0m");
+            }
+            buf.push('\n');
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(buf.as_bytes());
+    let _ = out.flush();
+}
+
+// which cube axis (0 red, 1 green, 2 blue) picks the face, the row and the column
+#[derive(Clone, Copy)]
+pub struct AxisOrder([usize; 3]);
+
+impl AxisOrder {
+    pub const RGB: Self = Self([0, 1, 2]);
+
+    // a permutation of `rgb`, e.g. `bgr` for faces by blue and columns by red
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut axes = [0; 3];
+        let mut seen = [false; 3];
+        let name = name.to_lowercase();
+        if name.chars().count() != 3 {
+            return None;
+        }
+        for (i, c) in name.chars().enumerate() {
+            let axis = "rgb".find(c)?;
+            if seen[axis] {
+                return None;
+            }
+            seen[axis] = true;
+            axes[i] = axis;
+        }
+        Some(Self(axes))
+    }
+
+    fn cube_index(self, face: u8, row: u8, col: u8) -> u8 {
+        let mut level = [0; 3];
+        level[self.0[0]] = face;
+        level[self.0[1]] = row;
+        level[self.0[2]] = col;
+        16 + 36 * level[0] + 6 * level[1] + level[2]
+    }
+
+    fn name(self) -> String {
+        self.0.iter().map(|&axis| ['r', 'g', 'b'][axis]).collect()
+    }
+}
+
+fn push_ansi_cell(buf: &mut String, code: u8, width: usize, numbered: bool) {
+    let color = ColorValue::Ansi(code);
+    push_background(buf, color);
+    if numbered {
+        push_contrast_text(buf, color);
+        let _ = write!(buf, "{:^width$}", code, width = width);
+    } else {
+        buf.push_str(&" ".repeat(width));
+    }
+    buf.push_str("\x1b[0m");
+}
+
+// the whole 256 color palette laid out by its structure: system colors, the cube
+// as six faces, and the grayscale ramp
+pub fn print_xterm_chart(width: u8, numbered: bool, axes: AxisOrder) {
+    let width = if numbered { (width as usize).max(4) } else { (width as usize).max(1) };
+    let term_width = terminal_size().map(|(w, _)| w as usize).unwrap_or(80);
+    let mut buf = String::new();
+
+    buf.push_str("\x1b[2msystem\x1b[22m\n");
+    for row in [0..8u8, 8..16] {
+        for code in row {
+            push_ansi_cell(&mut buf, code, width, numbered);
+        }
+        buf.push('\n');
+    }
+
+    let axis_names: Vec<char> = axes.name().chars().collect();
+    let _ = writeln!(
+        buf,
+        "\n\x1b[2mcube: faces by {}, rows by {}, columns by {}\x1b[22m",
+        axis_names[0], axis_names[1], axis_names[2]
+    );
+    // faces sit side by side, two spaces apart, as many as fit
+    let faces_per_row = ((term_width + 2) / (6 * width + 2)).clamp(1, 6);
+    for faces in (0..6u8).collect::<Vec<_>>().chunks(faces_per_row) {
+        for row in 0..6 {
+            for (i, &face) in faces.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str("  ");
+                }
+                for col in 0..6 {
+                    push_ansi_cell(&mut buf, axes.cube_index(face, row, col), width, numbered);
+                }
+            }
+            buf.push('\n');
+        }
+        buf.push('\n');
+    }
+
+    buf.push_str("\x1b[2mgrayscale\x1b[22m\n");
+    let ramp: Vec<u8> = (232..=255).collect();
+    for chunk in ramp.chunks((term_width / width).clamp(1, 24)) {
+        for &code in chunk {
+            push_ansi_cell(&mut buf, code, width, numbered);
+        }
+        buf.push('\n');
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(buf.as_bytes());
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_for_each_notation() {
+        let red = ColorValue::Ansi(1);
+        let hex = ColorValue::Rgb(0xff, 0x87, 0x00);
+        assert_eq!(LabelKind::Hex.text("red", red), "#cd0000");
+        assert_eq!(LabelKind::Index.text("#ff8700", hex), "~208");
+        assert_eq!(LabelKind::Name.text("1", red), "red");
+        assert_eq!(LabelKind::Name.text("#FF8700", hex), "#ff8700");
+    }
+
+    #[test]
+    fn axis_order_permutes_cube_levels() {
+        // face 1, row 2, column 3
+        assert_eq!(AxisOrder::RGB.cube_index(1, 2, 3), 16 + 36 + 12 + 3);
+        assert_eq!(AxisOrder::from_name("bgr").unwrap().cube_index(1, 2, 3), 16 + 3 * 36 + 12 + 1);
+        assert!(AxisOrder::from_name("rrg").is_none());
+        assert!(AxisOrder::from_name("rgba").is_none());
+    }
+}
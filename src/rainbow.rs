@@ -1,85 +1,331 @@
 use std::{
-    io::{self, stdout, BufWriter, Write}, thread, time::Duration, sync::atomic::{AtomicBool, Ordering}
+    io::{self, stdout, BufWriter, Write}, thread, time::{Duration, Instant}, sync::atomic::{AtomicBool, AtomicU64, Ordering}
 };
 
-use crate::terminal::{terminal_size, enable_raw_mode, disable_raw_mode, InputHandler, clear_screen};
+use crate::animation::{Animation, AnimationKind, Grid};
+use crate::ansi::{push_color, ColorDepth};
+use crate::cast::CastWriter;
+use crate::color::{hsl_to_rgb, hsv_to_rgb, linear_to_srgb, to_u8, Oklch};
+use crate::hex::StripOptions;
+use crate::terminal::{terminal_size, enable_raw_mode, disable_raw_mode, restore_from_signal, set_signal_handler, write_raw, InputHandler, Key, clear_screen};
 use crate::rng::SimpleRng;
-use std::simd::{cmp::SimdPartialOrd, prelude::{Simd, SimdPartialEq}};
+use crate::shade::push_gradient;
+use std::simd::{cmp::{SimdOrd, SimdPartialOrd}, num::SimdUint, prelude::{Simd, SimdPartialEq}, Select};
 
 //////////////////////////////////////////////////////////////////////////////////////////
 /// Random gradient looping per-cell, now with SIMD™ (Optimized)
 
 // number of SIMD lanes for u8's
 #[cfg(target_feature = "avx512f")]
-const LANES: usize = 64;
+pub const LANES: usize = 64;
 #[cfg(all(not(target_feature = "avx512f"), target_feature = "avx2"))]
-const LANES: usize = 32;
+pub const LANES: usize = 32;
 #[cfg(all(not(target_feature = "avx512f"), not(target_feature = "avx2"), target_feature = "sse2"))]
-const LANES: usize = 16;
+pub const LANES: usize = 16;
 #[cfg(not(any(target_feature = "avx512f", target_feature = "avx2", target_feature = "sse2")))]
-const LANES: usize = 1; // fallback to scalar
+pub const LANES: usize = 1; // fallback to scalar
+
+// half-width of the window `GoalMode::Near` picks new goals from
+const NEAR_RANGE: u8 = 64;
+
+// factor each speed up/down key press changes the animation speed by
+const SPEED_FACTOR: f32 = 1.25;
+const MAX_FPS: u32 = 240;
+
+// grids smaller than this aren't worth splitting across threads
+const PARALLEL_MIN_CELLS: usize = 32_768;
+
+const HELP_LINES: [(&str, &str); 12] = [
+    ("space, p", "pause / resume"),
+    ("., n", "single step while paused"),
+    ("up, +", "speed up"),
+    ("down, -", "slow down"),
+    ("right, left", "raise / lower fps"),
+    ("r", "reseed colors"),
+    ("h", "toggle half-block mode"),
+    ("c", "cycle color depth"),
+    ("a", "next animation"),
+    ("s", "cycle animation style"),
+    ("?", "toggle this help"),
+    ("q, esc", "quit"),
+];
 
 static CLEANUP_DONE: AtomicBool = AtomicBool::new(false);
+// seed of the current scene, reported on exit so it can be replayed with --seed
+static CURRENT_SEED: AtomicU64 = AtomicU64::new(0);
+
+// "seed: N" and a newline, formatted without allocating
+fn seed_line(seed: u64, buf: &mut [u8; 32]) -> &[u8] {
+    let mut end = buf.len();
+    buf[end - 1] = b'\n';
+    end -= 1;
+    let mut n = seed;
+    loop {
+        end -= 1;
+        buf[end] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    let start = end - 6;
+    buf[start..end].copy_from_slice(b"seed: ");
+    &buf[start..]
+}
 
+// only async-signal-safe calls: the interrupted thread may hold the stdout or stderr lock
 extern "C" fn signal_handler(_: libc::c_int) {
-    if !CLEANUP_DONE.load(Ordering::Relaxed) {
-        let _ = disable_raw_mode();
-        CLEANUP_DONE.store(true, Ordering::Relaxed);
+    if !CLEANUP_DONE.swap(true, Ordering::Relaxed) {
+        restore_from_signal();
+        let mut buf = [0; 32];
+        write_raw(2, seed_line(CURRENT_SEED.load(Ordering::Relaxed), &mut buf));
+    }
+    unsafe { libc::_exit(0) }
+}
+
+// how a cell picks its next color once it reaches its goal
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GoalMode {
+    Random, // any color
+    Near,   // a color close to the current one
+    Gray,   // a random shade of gray
+}
+
+impl GoalMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "random" => Some(Self::Random),
+            "near"   => Some(Self::Near),
+            "gray" | "grey" => Some(Self::Gray),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Near => "near",
+            Self::Gray => "gray",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Random => Self::Near,
+            Self::Near => Self::Gray,
+            Self::Gray => Self::Random,
+        }
+    }
+}
+
+// what a key press does while animating
+enum Action {
+    Quit,
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    FpsUp,
+    FpsDown,
+    Reseed,
+    ToggleHalfBlock,
+    CycleDepth,
+    CycleAnimation,
+    CycleStyle,
+    ToggleHelp,
+}
+
+impl Action {
+    fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::Char('q') | Key::Char('Q') | Key::Escape | Key::CtrlC => Some(Self::Quit),
+            Key::Char(' ') | Key::Char('p') => Some(Self::TogglePause),
+            Key::Char('.') | Key::Char('n') => Some(Self::Step),
+            Key::Char('+') | Key::Char('=') | Key::Up => Some(Self::Faster),
+            Key::Char('-') | Key::Down => Some(Self::Slower),
+            Key::Right => Some(Self::FpsUp),
+            Key::Left => Some(Self::FpsDown),
+            Key::Char('r') => Some(Self::Reseed),
+            Key::Char('h') => Some(Self::ToggleHalfBlock),
+            Key::Char('c') => Some(Self::CycleDepth),
+            Key::Char('a') => Some(Self::CycleAnimation),
+            Key::Char('s') => Some(Self::CycleStyle),
+            Key::Char('?') => Some(Self::ToggleHelp),
+            _ => None,
+        }
     }
-    std::process::exit(0);
 }
 
-pub fn crazyfn() -> io::Result<()> {
-    unsafe {
-        libc::signal(libc::SIGINT, signal_handler as usize);
-        libc::signal(libc::SIGTERM, signal_handler as usize);
+#[derive(Clone)]
+pub struct CrazyOptions {
+    pub fps: u32,        // target frames per second
+    pub fade_speed: f32, // channel steps per second
+    pub goal: GoalMode,
+    pub animation: AnimationKind,
+    pub seed: u64,
+    pub scalar: bool,    // skip the SIMD path of the fade
+    pub stats: bool,     // show fps/render time overlay
+    pub record: Option<String>, // asciicast file to record frames to
+    pub threads: usize,  // 0 picks a count from the grid size and cpu count
+}
+
+// smoothed frame timing for the stats overlay
+struct FrameStats {
+    fps: f32,
+    render_ms: f32,
+}
+
+impl FrameStats {
+    const SMOOTHING: f32 = 0.1;
+
+    fn new() -> Self {
+        Self { fps: 0.0, render_ms: 0.0 }
+    }
+
+    // the first frame has no previous one to measure against (dt == 0)
+    fn update(&mut self, dt: f32, render_time: Duration) {
+        let render_ms = render_time.as_secs_f32() * 1000.0;
+        if dt <= 0.0 {
+            return;
+        }
+        if self.fps == 0.0 {
+            self.fps = 1.0 / dt;
+            self.render_ms = render_ms;
+        } else {
+            self.fps += (1.0 / dt - self.fps) * Self::SMOOTHING;
+            self.render_ms += (render_ms - self.render_ms) * Self::SMOOTHING;
+        }
+    }
+
+    fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "\x1b[H\x1b[0m {:.1} fps | render {:.2} ms ", self.fps, self.render_ms)
     }
+}
 
+pub fn crazyfn(opts: &CrazyOptions) -> io::Result<()> {
+    let mut buffer = Buffer::new(opts);
+    let mut stdout: Box<dyn Write> = match &opts.record {
+        Some(path) => Box::new(CastWriter::create(path, BufWriter::new(stdout()), buffer.width, buffer.rows(), "clrblk --crazy")?),
+        None => Box::new(BufWriter::new(stdout())),
+    };
+
+    set_signal_handler(signal_handler);
     enable_raw_mode()?;
     clear_screen()?;
     
-    let mut stdout = BufWriter::new(stdout());
-    let mut buffer = Buffer::new();
     let input = InputHandler::new();
+    let mut fps = opts.fps.max(1);
+    let mut stats = FrameStats::new();
+    let mut paused = false;
+    let mut show_help = false;
 
     let result = (|| -> io::Result<()> {
-        loop {
-            if input.check_exit() {
-                break;
+        let mut last: Option<Instant> = None;
+        'frames: loop {
+            let mut step = false;
+            for key in input.read_keys() {
+                match Action::from_key(key) {
+                    Some(Action::Quit) => break 'frames,
+                    Some(Action::TogglePause) => paused = !paused,
+                    Some(Action::Step) => step = true,
+                    Some(Action::Faster) => buffer.speed *= SPEED_FACTOR,
+                    Some(Action::Slower) => buffer.speed /= SPEED_FACTOR,
+                    Some(Action::FpsUp) => fps = (fps + 5).min(MAX_FPS),
+                    Some(Action::FpsDown) => fps = fps.saturating_sub(5).max(1),
+                    Some(Action::Reseed) => buffer.reseed(),
+                    Some(Action::ToggleHalfBlock) => buffer.half_block = !buffer.half_block,
+                    Some(Action::CycleDepth) => buffer.depth = buffer.depth.next(),
+                    Some(Action::CycleAnimation) => buffer.next_animation(),
+                    Some(Action::CycleStyle) => buffer.animation.next_style(),
+                    Some(Action::ToggleHelp) => show_help = !show_help,
+                    None => {}
+                }
             }
-            
+
+            let frame_start = Instant::now();
+            let dt = last.map_or(0.0, |l| frame_start.duration_since(l).as_secs_f32());
+            last = Some(frame_start);
+
             buffer.resize();
-            buffer.tick();
+            if !paused {
+                buffer.advance(dt);
+            } else if step {
+                buffer.step(1.0 / fps as f32);
+            }
             buffer.render(&mut stdout)?;
+            if opts.stats {
+                stats.render(&mut stdout)?;
+            }
+            if show_help {
+                render_help(&mut stdout, &buffer, fps, paused)?;
+            }
             stdout.flush()?;
-            thread::sleep(Duration::from_millis(20));
+            stats.update(dt, frame_start.elapsed());
+
+            let frame_time = Duration::from_secs_f64(1.0 / fps as f64);
+            thread::sleep(frame_time.saturating_sub(frame_start.elapsed()));
         }
         Ok(())
     })();
 
     disable_raw_mode()?;
+    eprintln!("seed: {}", buffer.seed);
     CLEANUP_DONE.store(true, Ordering::Relaxed);
     
     result
 }
 
-struct Buffer {
+// help overlay listing the controls and current settings
+fn render_help(out: &mut impl Write, buffer: &Buffer, fps: u32, paused: bool) -> io::Result<()> {
+    let animation = match buffer.animation.style() {
+        Some(style) => format!("{} ({})", buffer.kind.name(), style),
+        None => buffer.kind.name().to_string(),
+    };
+    let status = format!(
+        "{} | {} | x{:.2} | {} fps | {} | {}",
+        if paused { "paused" } else { "running" },
+        animation,
+        buffer.speed,
+        fps,
+        buffer.depth.name(),
+        if buffer.half_block { "half blocks" } else { "full blocks" },
+    );
+
+    write!(out, "\x1b[0m")?;
+    write!(out, "\x1b[3;3H  {:<52}  ", status)?;
+    write!(out, "\x1b[4;3H  {:<52}  ", "")?;
+    for (i, (keys, description)) in HELP_LINES.iter().enumerate() {
+        write!(out, "\x1b[{};3H  {:<12}{:<40}  ", i + 5, keys, description)?;
+    }
+    write!(out, "\x1b[{};3H  {:<52}  ", HELP_LINES.len() + 5, "")
+}
+
+pub struct Buffer {
     width: u16,
-    height: u16,
+    height: u16, // in pixels, two per terminal row in half-block mode
     pixels: PixelBuffer,
-    goals: PixelBuffer,
+    seed: u64,
     rng: SimpleRng,
+    kind: AnimationKind,
+    animation: Box<dyn Animation>,
+    speed: f32, // animation time multiplier
+    fade_speed: f32,
+    goal: GoalMode,
+    scalar: bool,
+    threads: usize,
+    depth: ColorDepth,
+    half_block: bool,
 }
 
 #[repr(align(64))]  // align to cache line boundary
-struct PixelBuffer {
-    r: Vec<u8>,
-    g: Vec<u8>,
-    b: Vec<u8>,
+pub struct PixelBuffer {
+    pub r: Vec<u8>,
+    pub g: Vec<u8>,
+    pub b: Vec<u8>,
 }
 
 impl PixelBuffer {
-    fn new(size: usize) -> Self {
+    pub fn new(size: usize) -> Self {
         Self {
             r: vec![0; size],
             g: vec![0; size],
@@ -93,7 +339,7 @@ impl PixelBuffer {
         self.b.resize(new_size, 0);
     }
 
-    fn fill_random(&mut self, rng: &mut SimpleRng) {
+    pub fn fill_random(&mut self, rng: &mut SimpleRng) {
         rng.fill(&mut self.r[..]);
         rng.fill(&mut self.g[..]);
         rng.fill(&mut self.b[..]);
@@ -101,43 +347,255 @@ impl PixelBuffer {
 }
 
 impl Buffer {
-    fn new() -> Self {
+    fn new(opts: &CrazyOptions) -> Self {
         let (w, h) = terminal_size().unwrap_or((80, 24));
+        Self::with_size(opts, w, h)
+    }
+
+    // a buffer that doesn't follow the terminal, for rendering off-screen
+    pub fn with_size(opts: &CrazyOptions, w: u16, h: u16) -> Self {
         let size = (w as usize) * (h as usize);
-        let mut rng = SimpleRng::new();
 
-        let mut pixels = PixelBuffer::new(size);
-        let mut goals = PixelBuffer::new(size);
+        CURRENT_SEED.store(opts.seed, Ordering::Relaxed);
+        let mut buffer = Buffer {
+            width: w,
+            height: h,
+            pixels: PixelBuffer::new(size),
+            seed: opts.seed,
+            rng: SimpleRng::with_seed(opts.seed),
+            kind: opts.animation,
+            animation: opts.animation.build(opts.fade_speed, opts.goal, opts.scalar),
+            speed: 1.0,
+            fade_speed: opts.fade_speed,
+            goal: opts.goal,
+            scalar: opts.scalar,
+            threads: opts.threads,
+            depth: ColorDepth::TrueColor,
+            half_block: false,
+        };
+        buffer.restart();
+        buffer
+    }
+
+    fn rows(&self) -> u16 {
+        if self.half_block { self.height / 2 } else { self.height }
+    }
+
+    fn threads(&self) -> usize {
+        match self.threads {
+            0 if (self.width as usize) * (self.height as usize) < PARALLEL_MIN_CELLS => 1,
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+    }
 
-        pixels.fill_random(&mut rng);
-        goals.fill_random(&mut rng);
+    fn grid(&self) -> Grid {
+        Grid {
+            width: self.width as usize,
+            height: self.height as usize,
+            aspect: if self.half_block { 1.0 } else { 2.0 },
+            threads: self.threads(),
+        }
+    }
 
-        Buffer { width: w, height: h, pixels, goals, rng }
+    fn restart(&mut self) {
+        let grid = self.grid();
+        self.animation.reset(&mut self.pixels, grid, &mut self.rng);
     }
 
+    // match the terminal size (and half-block mode), starting over on any change
     fn resize(&mut self) {
-        let (w, h) = terminal_size().unwrap_or((self.width, self.height));
+        let (w, rows) = terminal_size().unwrap_or((self.width, self.rows()));
+        let h = if self.half_block { rows * 2 } else { rows };
         if w != self.width || h != self.height {
             self.width = w;
             self.height = h;
-            let size = (w as usize) * (h as usize);
+            self.pixels.resize((w as usize) * (h as usize));
+            self.restart();
+        }
+    }
+
+    // the next seed comes from the current one, so a whole session replays from the first
+    fn reseed(&mut self) {
+        self.seed = self.rng.next_u64();
+        self.rng = SimpleRng::with_seed(self.seed);
+        CURRENT_SEED.store(self.seed, Ordering::Relaxed);
+        self.restart();
+    }
+
+    fn next_animation(&mut self) {
+        self.kind = self.kind.next();
+        self.animation = self.kind.build(self.fade_speed, self.goal, self.scalar);
+        self.restart();
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        let grid = self.grid();
+        self.animation.update(&mut self.pixels, grid, dt * self.speed, &mut self.rng);
+    }
+
+    // a single frame while paused
+    pub fn step(&mut self, dt: f32) {
+        let grid = self.grid();
+        self.animation.step(&mut self.pixels, grid, dt * self.speed, &mut self.rng);
+    }
 
-            self.pixels.resize(size);
-            self.goals.resize(size);
+    pub fn render(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "\x1b[H\x1b[2J")?; // Direct ANSI: cursor home + clear screen
 
-            self.pixels.fill_random(&mut self.rng);
-            self.goals.fill_random(&mut self.rng);
+        let rows = self.rows() as usize;
+        let (pixels, width, depth, half_block) = (&self.pixels, self.width as usize, self.depth, self.half_block);
+        let threads = self.threads().min(rows.max(1));
+        if threads <= 1 {
+            out.write_all(render_rows(pixels, width, rows, depth, half_block, 0..rows).as_bytes())?;
+        } else {
+            // each band renders into its own buffer, written out in order
+            let rows_per_band = rows.div_ceil(threads);
+            let bands: Vec<String> = thread::scope(|s| {
+                let handles: Vec<_> = (0..rows)
+                    .step_by(rows_per_band)
+                    .map(|start| {
+                        let range = start..(start + rows_per_band).min(rows);
+                        s.spawn(move || render_rows(pixels, width, rows, depth, half_block, range))
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap_or_default()).collect()
+            });
+            for band in &bands {
+                out.write_all(band.as_bytes())?;
+            }
         }
+
+        write!(out, "\x1b[0m")?;
+        Ok(())
     }
+}
 
-    fn tick(&mut self) {
-        let len = self.pixels.r.len();
-        let chunks = len / LANES;
 
-        // pre-generate random goals for all chunks
-        let total_random_needed = chunks * LANES * 3;
-        let mut rng_buffer = vec![0u8; total_random_needed];
-        self.rng.fill(&mut rng_buffer[..]);
+// escape sequences for a range of terminal rows, shared by all render threads
+pub fn render_rows(pixels: &PixelBuffer, width: usize, rows: usize, depth: ColorDepth, half_block: bool, range: std::ops::Range<usize>) -> String {
+    let mut frame_buffer = String::with_capacity(
+        width * range.len() * if half_block { 40 } else { 20 }
+    );
+
+    for row in range {
+        for col in 0..width {
+            if half_block {
+                // upper pixel in the foreground of '▀', lower pixel in the background
+                let top = 2 * row * width + col;
+                let bottom = top + width;
+                push_color(&mut frame_buffer, depth, true, pixels.r[top], pixels.g[top], pixels.b[top]);
+                push_color(&mut frame_buffer, depth, false, pixels.r[bottom], pixels.g[bottom], pixels.b[bottom]);
+                frame_buffer.push('▀');
+            } else {
+                let idx = row * width + col;
+                push_color(&mut frame_buffer, depth, false, pixels.r[idx], pixels.g[idx], pixels.b[idx]);
+                frame_buffer.push(' ');
+            }
+        }
+        if row + 1 < rows {
+            frame_buffer.push_str("\r\n");
+        }
+    }
+    frame_buffer
+}
+
+
+// fades every cell towards its own random goal color
+pub struct Fade {
+    goals: PixelBuffer,
+    fade_speed: f32, // channel steps per second
+    fade_accum: f32, // fractional steps carried over between frames
+    goal_mode: GoalMode,
+    scalar: bool, // run every cell through `process_remaining_elements`
+}
+
+// a contiguous run of cells: their colors, goals and this tick's random bytes
+struct FadeBand<'a> {
+    r: &'a mut [u8],
+    g: &'a mut [u8],
+    b: &'a mut [u8],
+    gr: &'a mut [u8],
+    gg: &'a mut [u8],
+    gb: &'a mut [u8],
+    rnd_r: &'a [u8],
+    rnd_g: &'a [u8],
+    rnd_b: &'a [u8],
+}
+
+impl<'a> FadeBand<'a> {
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let (r1, r2) = self.r.split_at_mut(mid);
+        let (g1, g2) = self.g.split_at_mut(mid);
+        let (b1, b2) = self.b.split_at_mut(mid);
+        let (gr1, gr2) = self.gr.split_at_mut(mid);
+        let (gg1, gg2) = self.gg.split_at_mut(mid);
+        let (gb1, gb2) = self.gb.split_at_mut(mid);
+        let (rr1, rr2) = self.rnd_r.split_at(mid);
+        let (rg1, rg2) = self.rnd_g.split_at(mid);
+        let (rb1, rb2) = self.rnd_b.split_at(mid);
+        (
+            Self { r: r1, g: g1, b: b1, gr: gr1, gg: gg1, gb: gb1, rnd_r: rr1, rnd_g: rg1, rnd_b: rb1 },
+            Self { r: r2, g: g2, b: b2, gr: gr2, gg: gg2, gb: gb2, rnd_r: rr2, rnd_g: rg2, rnd_b: rb2 },
+        )
+    }
+}
+
+impl Fade {
+    pub fn new(fade_speed: f32, goal_mode: GoalMode, scalar: bool) -> Self {
+        Self { goals: PixelBuffer::new(0), fade_speed, fade_accum: 0.0, goal_mode, scalar }
+    }
+
+    fn tick(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng, step: u8) {
+        let len = pixels.r.len();
+
+        // pre-generate random goals for every cell, one plane per channel so each
+        // cell draws the same bytes whatever the lane count or thread count
+        // (seeds reproduce across CPUs)
+        let mut rng_buffer = vec![0u8; len * 3];
+        rng.fill(&mut rng_buffer[..]);
+        let (rnd_r, rest) = rng_buffer.split_at(len);
+        let (rnd_g, rnd_b) = rest.split_at(len);
+
+        let mut band = FadeBand {
+            r: &mut pixels.r[..],
+            g: &mut pixels.g[..],
+            b: &mut pixels.b[..],
+            gr: &mut self.goals.r[..],
+            gg: &mut self.goals.g[..],
+            gb: &mut self.goals.b[..],
+            rnd_r,
+            rnd_g,
+            rnd_b,
+        };
+        let (goal_mode, scalar) = (self.goal_mode, self.scalar);
+
+        if grid.threads <= 1 {
+            Self::tick_band(band, step, goal_mode, scalar);
+            return;
+        }
+
+        // row bands, each a whole number of SIMD chunks where possible
+        let rows_per_band = grid.height.div_ceil(grid.threads);
+        let band_len = (rows_per_band * grid.width).next_multiple_of(LANES);
+        thread::scope(|s| {
+            while band.r.len() > band_len {
+                let (head, tail) = band.split_at(band_len);
+                s.spawn(move || Self::tick_band(head, step, goal_mode, scalar));
+                band = tail;
+            }
+            Self::tick_band(band, step, goal_mode, scalar);
+        });
+    }
+
+    fn tick_band(mut band: FadeBand, step: u8, goal_mode: GoalMode, scalar: bool) {
+        let len = band.r.len();
+        if scalar {
+            Self::process_remaining_elements(&mut band, 0, len, step, goal_mode);
+            return;
+        }
+
+        let chunks = len / LANES;
 
         // process chunks with manual loop unrolling for maximum SIMD throughput
         let mut chunk_idx = 0;
@@ -150,37 +608,37 @@ impl Buffer {
             let base3 = (chunk_idx + 2) * LANES;
             let base4 = (chunk_idx + 3) * LANES;
             
-            self.process_chunk(base1, &rng_buffer, chunk_idx);
-            self.process_chunk(base2, &rng_buffer, chunk_idx + 1);
-            self.process_chunk(base3, &rng_buffer, chunk_idx + 2);
-            self.process_chunk(base4, &rng_buffer, chunk_idx + 3);
+            Self::process_chunk(&mut band, base1, step, goal_mode);
+            Self::process_chunk(&mut band, base2, step, goal_mode);
+            Self::process_chunk(&mut band, base3, step, goal_mode);
+            Self::process_chunk(&mut band, base4, step, goal_mode);
             
             chunk_idx += unroll_factor;
         }
 
         for i in chunk_idx..chunks {
             let base = i * LANES;
-            self.process_chunk(base, &rng_buffer, i);
+            Self::process_chunk(&mut band, base, step, goal_mode);
         }
 
         // process remainder
         let remaining = len % LANES;
         if remaining != 0 {
-            self.process_remaining_elements(chunks * LANES, remaining);
+            Self::process_remaining_elements(&mut band, chunks * LANES, remaining, step, goal_mode);
         }
     }
 
     #[inline(always)]
-    fn process_chunk(&mut self, base: usize, rng_buffer: &[u8], chunk_idx: usize) {
+    fn process_chunk(band: &mut FadeBand, base: usize, step: u8, goal_mode: GoalMode) {
         // load up the SIMD registers
-        let r_vec = Simd::<u8, LANES>::from_slice(&self.pixels.r[base..base + LANES]);
-        let g_vec = Simd::<u8, LANES>::from_slice(&self.pixels.g[base..base + LANES]);
-        let b_vec = Simd::<u8, LANES>::from_slice(&self.pixels.b[base..base + LANES]);
-        let gr_vec = Simd::<u8, LANES>::from_slice(&self.goals.r[base..base + LANES]);
-        let gg_vec = Simd::<u8, LANES>::from_slice(&self.goals.g[base..base + LANES]);
-        let gb_vec = Simd::<u8, LANES>::from_slice(&self.goals.b[base..base + LANES]);
+        let r_vec = Simd::<u8, LANES>::from_slice(&band.r[base..base + LANES]);
+        let g_vec = Simd::<u8, LANES>::from_slice(&band.g[base..base + LANES]);
+        let b_vec = Simd::<u8, LANES>::from_slice(&band.b[base..base + LANES]);
+        let gr_vec = Simd::<u8, LANES>::from_slice(&band.gr[base..base + LANES]);
+        let gg_vec = Simd::<u8, LANES>::from_slice(&band.gg[base..base + LANES]);
+        let gb_vec = Simd::<u8, LANES>::from_slice(&band.gb[base..base + LANES]);
 
-        let one = Simd::splat(1u8);
+        let step = Simd::splat(step);
 
         // check less than or greater than
         let r_lt = r_vec.simd_lt(gr_vec);
@@ -190,142 +648,349 @@ impl Buffer {
         let b_lt = b_vec.simd_lt(gb_vec);
         let b_gt = b_vec.simd_gt(gb_vec);
 
-        // step towards goal
-        let r_new = r_lt.select(r_vec + one, r_gt.select(r_vec - one, r_vec));
-        let g_new = g_lt.select(g_vec + one, g_gt.select(g_vec - one, g_vec));
-        let b_new = b_lt.select(b_vec + one, b_gt.select(b_vec - one, b_vec));
+        // step towards goal without overshooting it
+        let r_new = r_lt.select(r_vec.saturating_add(step).simd_min(gr_vec), r_gt.select(r_vec.saturating_sub(step).simd_max(gr_vec), r_vec));
+        let g_new = g_lt.select(g_vec.saturating_add(step).simd_min(gg_vec), g_gt.select(g_vec.saturating_sub(step).simd_max(gg_vec), g_vec));
+        let b_new = b_lt.select(b_vec.saturating_add(step).simd_min(gb_vec), b_gt.select(b_vec.saturating_sub(step).simd_max(gb_vec), b_vec));
 
         // write the new colors
-        r_new.copy_to_slice(&mut self.pixels.r[base..base + LANES]);
-        g_new.copy_to_slice(&mut self.pixels.g[base..base + LANES]);
-        b_new.copy_to_slice(&mut self.pixels.b[base..base + LANES]);
+        r_new.copy_to_slice(&mut band.r[base..base + LANES]);
+        g_new.copy_to_slice(&mut band.g[base..base + LANES]);
+        b_new.copy_to_slice(&mut band.b[base..base + LANES]);
 
         // check for reached goal
         let done = r_new.simd_eq(gr_vec) & g_new.simd_eq(gg_vec) & b_new.simd_eq(gb_vec);
 
         // load pre-generated random goals
-        let rng_base = chunk_idx * LANES * 3;
-        let new_gr = Simd::from_slice(&rng_buffer[rng_base..rng_base + LANES]);
-        let new_gg = Simd::from_slice(&rng_buffer[rng_base + LANES..rng_base + 2 * LANES]);
-        let new_gb = Simd::from_slice(&rng_buffer[rng_base + 2 * LANES..rng_base + 3 * LANES]);
+        let rnd_r = Simd::from_slice(&band.rnd_r[base..base + LANES]);
+        let rnd_g = Simd::from_slice(&band.rnd_g[base..base + LANES]);
+        let rnd_b = Simd::from_slice(&band.rnd_b[base..base + LANES]);
+
+        let (new_gr, new_gg, new_gb) = match goal_mode {
+            GoalMode::Random => (rnd_r, rnd_g, rnd_b),
+            GoalMode::Gray => (rnd_r, rnd_r, rnd_r),
+            GoalMode::Near => {
+                // offset in [-NEAR_RANGE, NEAR_RANGE) around the current color
+                let mask = Simd::splat(2 * NEAR_RANGE - 1);
+                let range = Simd::splat(NEAR_RANGE);
+                (
+                    r_new.saturating_add(rnd_r & mask).saturating_sub(range),
+                    g_new.saturating_add(rnd_g & mask).saturating_sub(range),
+                    b_new.saturating_add(rnd_b & mask).saturating_sub(range),
+                )
+            }
+        };
 
         // store new goals (masked to completed cells)
-        new_gr.store_select(&mut self.goals.r[base..base + LANES], done);
-        new_gg.store_select(&mut self.goals.g[base..base + LANES], done);
-        new_gb.store_select(&mut self.goals.b[base..base + LANES], done);
+        new_gr.store_select(&mut band.gr[base..base + LANES], done);
+        new_gg.store_select(&mut band.gg[base..base + LANES], done);
+        new_gb.store_select(&mut band.gb[base..base + LANES], done);
     }
 
-    fn process_remaining_elements(&mut self, start: usize, remaining: usize) {
+    fn process_remaining_elements(band: &mut FadeBand, start: usize, remaining: usize, step: u8, goal_mode: GoalMode) {
         // scalar fallback
         for i in start..start + remaining {
             // step towards goal
-            self.pixels.r[i] = self.step_towards_goal(self.pixels.r[i], self.goals.r[i]);
-            self.pixels.g[i] = self.step_towards_goal(self.pixels.g[i], self.goals.g[i]);
-            self.pixels.b[i] = self.step_towards_goal(self.pixels.b[i], self.goals.b[i]);
+            band.r[i] = Self::step_towards_goal(band.r[i], band.gr[i], step);
+            band.g[i] = Self::step_towards_goal(band.g[i], band.gg[i], step);
+            band.b[i] = Self::step_towards_goal(band.b[i], band.gb[i], step);
 
             // check if goal is reached and assign new goal if so
-            if self.pixels.r[i] == self.goals.r[i] && 
-               self.pixels.g[i] == self.goals.g[i] && 
-               self.pixels.b[i] == self.goals.b[i] {
-                self.goals.r[i] = self.rng.random();
-                self.goals.g[i] = self.rng.random();
-                self.goals.b[i] = self.rng.random();
+            if band.r[i] == band.gr[i] && 
+               band.g[i] == band.gg[i] && 
+               band.b[i] == band.gb[i] {
+                let (r, g, b) = (band.rnd_r[i], band.rnd_g[i], band.rnd_b[i]);
+                match goal_mode {
+                    GoalMode::Random => {
+                        band.gr[i] = r;
+                        band.gg[i] = g;
+                        band.gb[i] = b;
+                    }
+                    GoalMode::Gray => {
+                        band.gr[i] = r;
+                        band.gg[i] = r;
+                        band.gb[i] = r;
+                    }
+                    GoalMode::Near => {
+                        band.gr[i] = near_goal(band.r[i], r);
+                        band.gg[i] = near_goal(band.g[i], g);
+                        band.gb[i] = near_goal(band.b[i], b);
+                    }
+                }
             }
         }
     }
 
     #[inline(always)]
-    fn step_towards_goal(&self, current: u8, goal: u8) -> u8 {
+    fn step_towards_goal(current: u8, goal: u8, step: u8) -> u8 {
         match current.cmp(&goal) {
-            std::cmp::Ordering::Less => current + 1,
-            std::cmp::Ordering::Greater => current - 1,
+            std::cmp::Ordering::Less => current.saturating_add(step).min(goal),
+            std::cmp::Ordering::Greater => current.saturating_sub(step).max(goal),
             std::cmp::Ordering::Equal => current,
         }
     }
 
-    fn render(&self, out: &mut impl Write) -> io::Result<()> {
-        write!(out, "\x1b[H\x1b[2J")?; // Direct ANSI: cursor home + clear screen
-        
-        let mut frame_buffer = String::with_capacity(
-            (self.width as usize) * (self.height as usize) * 20
-        );
-
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = (row as usize) * (self.width as usize) + (col as usize);
-                frame_buffer.push_str(&format!(
-                    "\x1b[48;2;{};{};{}m ", 
-                    self.pixels.r[idx], 
-                    self.pixels.g[idx], 
-                    self.pixels.b[idx]
-                ));
-            }
-            if row < self.height - 1 {
-                frame_buffer.push_str("\r\n");
-            }
+}
+
+impl Animation for Fade {
+    fn reset(&mut self, pixels: &mut PixelBuffer, grid: Grid, rng: &mut SimpleRng) {
+        self.goals.resize(grid.size());
+        pixels.fill_random(rng);
+        self.goals.fill_random(rng);
+    }
+
+    // advance the fade by `dt` seconds worth of steps
+    fn update(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        self.fade_accum += self.fade_speed * dt;
+        let whole = self.fade_accum.floor();
+        self.fade_accum -= whole;
+
+        let step = whole.min(255.0) as u8;
+        if step > 0 {
+            self.tick(pixels, grid, rng, step);
         }
-        frame_buffer.push_str("\x1b[0m");
+    }
 
-        write!(out, "{}", frame_buffer)?;
-        Ok(())
+    // one tick of a frame's worth of steps, and at least one, leaving the carried
+    // over fraction alone
+    fn step(&mut self, pixels: &mut PixelBuffer, grid: Grid, dt: f32, rng: &mut SimpleRng) {
+        let step = (self.fade_speed * dt).round().clamp(1.0, 255.0) as u8;
+        self.tick(pixels, grid, rng, step);
+    }
+
+    fn style(&self) -> Option<&'static str> {
+        Some(self.goal_mode.name())
+    }
+
+    fn next_style(&mut self) {
+        self.goal_mode = self.goal_mode.next();
     }
 }
 
-/// End of random gradient looping per-cell
+// scalar version of the `GoalMode::Near` offset in `process_chunk`
+#[inline(always)]
+fn near_goal(current: u8, rnd: u8) -> u8 {
+    current.saturating_add(rnd & (2 * NEAR_RANGE - 1)).saturating_sub(NEAR_RANGE)
+}
+
+// End of random gradient looping per-cell
 //////////////////////////////////////////////////////////////////////////////////////////
 
 
-pub fn print_grayscale() {
-    let stdout = io::stdout();
-    let mut out = BufWriter::new(stdout.lock());
+// the hue circle --rainbow goes around
+#[derive(Clone, Copy, PartialEq)]
+pub enum HuePath {
+    Hsv,
+    Hsl,
+    Oklch, // constant lightness and chroma, so no band looks brighter than another
+}
 
-    for v in 0..=255 {
-        let _ = write!(out, "\x1b[48;2;{0};{0};{0}m ", v);
+impl HuePath {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hsv" => Some(Self::Hsv),
+            "hsl" => Some(Self::Hsl),
+            "oklch" => Some(Self::Oklch),
+            _ => None,
+        }
     }
-    let _ = writeln!(out);
+}
 
-    for v in (0..=255).rev() {
-        let _ = write!(out, "\x1b[48;2;{0};{0};{0}m ", v);
+// how --grayscale spaces its steps between black and white
+#[derive(Clone, Copy, PartialEq)]
+pub enum GraySteps {
+    Gamma,      // even sRGB values
+    Linear,     // even amounts of light
+    Perceptual, // even OKLab lightness
+}
+
+impl GraySteps {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "gamma" | "srgb" => Some(Self::Gamma),
+            "linear" => Some(Self::Linear),
+            "perceptual" | "oklab" => Some(Self::Perceptual),
+            _ => None,
+        }
     }
-    let _ = writeln!(out, "\x1b[0m");
+}
 
-    let _ = out.flush();
+pub struct RainbowOptions {
+    pub path: HuePath,
+    pub saturation: f32,
+    pub lightness: Option<f32>, // value for HSV, lightness for HSL and OKLCH
+    pub cycles: f32,
+    pub reverse: bool, // red to magenta to blue instead of red to yellow to green
 }
 
+// cells for a strip that takes `native` cells to show every step: the terminal's width
+// with --fit, otherwise no wider than the terminal so it doesn't wrap
+fn strip_cells(native: usize, fit_width: bool) -> usize {
+    match terminal_size() {
+        Ok((w, _)) if w >= 1 && fit_width => w as usize,
+        Ok((w, _)) if w >= 1 => native.min(w as usize),
+        _ => native,
+    }
+}
+
+pub fn grayscale_colors(steps: GraySteps, cells: usize) -> Vec<(u8, u8, u8)> {
+    let last = cells.saturating_sub(1).max(1) as f32;
+    (0..cells)
+        .map(|i| {
+            let t = i as f32 / last;
+            let v = match steps {
+                GraySteps::Gamma => to_u8(t),
+                GraySteps::Linear => to_u8(linear_to_srgb(t)),
+                GraySteps::Perceptual => Oklch { l: t, c: 0.0, h: 0.0 }.to_rgb().0,
+            };
+            (v, v, v)
+        })
+        .collect()
+}
+
+pub fn rainbow_colors(opts: &RainbowOptions, cells: usize) -> Vec<(u8, u8, u8)> {
+    let saturation = opts.saturation.clamp(0.0, 1.0);
+    let lightness = opts.lightness.map(|l| l.clamp(0.0, 1.0));
+    // OKLCH starts at red's hue and keeps the chroma every hue can reach at its lightness
+    let (start, oklch_lightness, chroma) = match opts.path {
+        HuePath::Oklch => {
+            let l = lightness.unwrap_or(0.7);
+            let reachable = (0..360).map(|h| Oklch::max_chroma(l, h as f32)).fold(f32::MAX, f32::min);
+            (Oklch::from_rgb(255, 0, 0).h, l, reachable * saturation)
+        },
+        _ => (0.0, 0.0, 0.0),
+    };
+
+    let sweep = if opts.reverse { -360.0 } else { 360.0 } * opts.cycles;
+    let last = cells.saturating_sub(1).max(1) as f32;
+    (0..cells)
+        .map(|i| {
+            let hue = start + sweep * i as f32 / last;
+            match opts.path {
+                HuePath::Hsv => hsv_to_rgb(hue, saturation, lightness.unwrap_or(1.0)),
+                HuePath::Hsl => hsl_to_rgb(hue, saturation, lightness.unwrap_or(0.5)),
+                HuePath::Oklch => Oklch { l: oklch_lightness, c: chroma, h: hue.rem_euclid(360.0) }.to_rgb(),
+            }
+        })
+        .collect()
+}
 
-pub fn print_rainbow() {
+// black to white and back, one level per cell unless narrowed to the terminal
+pub fn print_grayscale(steps: GraySteps, strip: &StripOptions) {
+    let ramp = grayscale_colors(steps, strip_cells(256, strip.fit_width));
+    let mut buf = String::new();
+    push_gradient(&mut buf, &ramp, strip.depth, strip.dither);
+    buf.push_str("\x1b[0m\n");
+
+    let reversed: Vec<(u8, u8, u8)> = ramp.into_iter().rev().collect();
+    push_gradient(&mut buf, &reversed, strip.depth, strip.dither);
+    buf.push_str("\x1b[0m\n");
+    print_buffered(&buf);
+}
+
+// every hue from red back to red, `cycles` times; at full HSV saturation and value
+// 6 x 256 cells show each step of the six RGB phases
+pub fn print_rainbow(opts: &RainbowOptions, strip: &StripOptions) {
+    // one cell per hue step of a single cycle at most, more cycles just step faster
+    let native = (6.0 * 256.0 * opts.cycles).ceil().clamp(2.0, 6.0 * 256.0) as usize;
+    let colors = rainbow_colors(opts, strip_cells(native, strip.fit_width));
+    let mut buf = String::new();
+    push_gradient(&mut buf, &colors, strip.depth, strip.dither);
+    buf.push_str("\x1b[0m\n");
+    print_buffered(&buf);
+}
+
+fn print_buffered(buf: &str) {
     let stdout = io::stdout();
     let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(buf.as_bytes());
+    let _ = out.flush();
+}
 
-    let mut r: u8 = 255;
-    let mut g: u8 = 0;
-    let mut b: u8 = 0;
-
-    for i in 0..=255 {
-        g = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(animation: AnimationKind, threads: usize) -> CrazyOptions {
+        CrazyOptions {
+            fps: 50,
+            fade_speed: 50.0,
+            goal: GoalMode::Random,
+            animation,
+            seed: 7,
+            scalar: false,
+            stats: false,
+            record: None,
+            threads,
+        }
     }
-    for i in (0..=255).rev() {
-        r = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+
+    fn frames(animation: AnimationKind, threads: usize, depth: ColorDepth, half_block: bool) -> Vec<Vec<u8>> {
+        let mut buffer = Buffer::with_size(&options(animation, threads), 61, 23);
+        buffer.depth = depth;
+        buffer.half_block = half_block;
+        (0..8)
+            .map(|_| {
+                buffer.advance(0.05);
+                let mut out = Vec::new();
+                buffer.render(&mut out).unwrap();
+                out
+            })
+            .collect()
     }
-    for i in 0..=255 {
-        b = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+
+    #[test]
+    fn stepping_while_paused_always_moves() {
+        for kind in [AnimationKind::Fade, AnimationKind::Fire, AnimationKind::Life] {
+            let opts = CrazyOptions { fade_speed: 5.0, ..options(kind, 1) };
+            let mut buffer = Buffer::with_size(&opts, 20, 10);
+            buffer.advance(0.0);
+            let before = (buffer.pixels.r.clone(), buffer.pixels.g.clone(), buffer.pixels.b.clone());
+            buffer.step(1.0 / 50.0);
+            assert!(before != (buffer.pixels.r.clone(), buffer.pixels.g.clone(), buffer.pixels.b.clone()), "{}", kind.name());
+        }
     }
-    for i in (0..=255).rev() {
-        g = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+
+    #[test]
+    fn threaded_frames_match_single_threaded() {
+        let mut kind = AnimationKind::Fade;
+        loop {
+            for (depth, half_block) in [(ColorDepth::TrueColor, false), (ColorDepth::Ansi256, true), (ColorDepth::Ansi16, false)] {
+                assert!(frames(kind, 1, depth, half_block) == frames(kind, 4, depth, half_block), "{}", kind.name());
+            }
+            kind = kind.next();
+            if kind == AnimationKind::Fade {
+                break;
+            }
+        }
     }
-    for i in 0..=255 {
-        r = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+
+    #[test]
+    fn formats_the_seed_without_allocating() {
+        let mut buf = [0; 32];
+        assert_eq!(seed_line(0, &mut buf), b"seed: 0\n");
+        assert_eq!(seed_line(u64::MAX, &mut buf), b"seed: 18446744073709551615\n");
     }
-    for i in (0..=255).rev() {
-        b = i;
-        let _ = write!(out, "\x1b[48;2;{};{};{}m ", r, g, b);
+
+    #[test]
+    fn rainbows_follow_the_chosen_hue_path() {
+        let opts = |path, reverse| RainbowOptions { path, saturation: 1.0, lightness: None, cycles: 1.0, reverse };
+        let hsv = rainbow_colors(&opts(HuePath::Hsv, false), 7);
+        assert_eq!(hsv, [(255, 0, 0), (255, 255, 0), (0, 255, 0), (0, 255, 255), (0, 0, 255), (255, 0, 255), (255, 0, 0)]);
+        assert_eq!(rainbow_colors(&opts(HuePath::Hsv, true), 7)[1], (255, 0, 255));
+        assert_eq!(rainbow_colors(&opts(HuePath::Hsl, false), 7)[2], (0, 255, 0));
+
+        // every OKLCH cell has the same lightness and chroma
+        let oklch: Vec<Oklch> = rainbow_colors(&opts(HuePath::Oklch, false), 36).iter().map(|&(r, g, b)| Oklch::from_rgb(r, g, b)).collect();
+        let chroma = oklch[0].c;
+        assert!(chroma > 0.05);
+        assert!(oklch.iter().all(|c| (c.l - 0.7).abs() < 0.01 && (c.c - chroma).abs() < 0.01));
     }
 
-    let _ = writeln!(out, "\x1b[0m");
-    let _ = out.flush();
-}
\ No newline at end of file
+    #[test]
+    fn grayscale_steps_in_light_or_lightness() {
+        let middle = |steps| grayscale_colors(steps, 3)[1].0;
+        assert_eq!([GraySteps::Gamma, GraySteps::Linear, GraySteps::Perceptual].map(middle), [128, 188, 99]);
+        assert_eq!(grayscale_colors(GraySteps::Perceptual, 2), [(0, 0, 0), (255, 255, 255)]);
+    }
+}
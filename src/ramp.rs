@@ -0,0 +1,121 @@
+use std::fmt::Write as _;
+
+use crate::{
+    color::{to_hex, ColorValue, Oklch},
+    export::ExportFormat,
+    palette::{push_background, push_contrast_text},
+};
+
+// step names design systems use for an 11 step scale
+const TAILWIND_STEPS: [u16; 11] = [50, 100, 200, 300, 400, 500, 600, 700, 800, 900, 950];
+// lightness of the lightest and darkest steps
+const LIGHTEST: f32 = 0.97;
+const DARKEST: f32 = 0.25;
+// share of the base chroma given up at the far ends of the scale
+const END_CHROMA_LOSS: f32 = 0.7;
+
+pub struct Ramp {
+    pub steps: Vec<(u16, (u8, u8, u8))>,
+    pub base_index: usize,
+}
+
+// `50..950` for 11 steps, otherwise even names between 0 and 1000 rounded to tens
+fn step_names(count: usize) -> Vec<u16> {
+    if count == TAILWIND_STEPS.len() {
+        TAILWIND_STEPS.to_vec()
+    } else {
+        (1..=count).map(|i| ((i * 1000 / (count + 1) + 5) / 10 * 10) as u16).collect()
+    }
+}
+
+impl Ramp {
+    // `count` steps from light to dark with lightness evenly spaced in OKLCH; the
+    // input lands unchanged on the step nearest its own lightness, with the steps
+    // either side spread evenly towards the ends
+    pub fn generate(base: (u8, u8, u8), count: usize) -> Self {
+        let count = count.max(2);
+        let lch = Oklch::from_rgb(base.0, base.1, base.2);
+        let even = |i: usize| LIGHTEST + (DARKEST - LIGHTEST) * i as f32 / (count - 1) as f32;
+        let base_index = (0..count)
+            .min_by(|&a, &b| (even(a) - lch.l).abs().total_cmp(&(even(b) - lch.l).abs()))
+            .unwrap_or(0);
+
+        let steps = step_names(count)
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                if i == base_index {
+                    return (name, base);
+                }
+                // 0 at the input, 1 at whichever end this step is heading to
+                let (end, distance) = if i < base_index {
+                    (LIGHTEST.max(lch.l), (base_index - i) as f32 / base_index as f32)
+                } else {
+                    (DARKEST.min(lch.l), (i - base_index) as f32 / (count - 1 - base_index) as f32)
+                };
+                let l = lch.l + (end - lch.l) * distance;
+                let c = lch.c * (1.0 - END_CHROMA_LOSS * distance * distance);
+                (name, Oklch { l, c, h: lch.h }.to_rgb())
+            })
+            .collect();
+
+        Self { steps, base_index }
+    }
+
+    pub fn export(&self, format: ExportFormat, name: &str) -> Option<String> {
+        let mut out = String::new();
+        match format {
+            ExportFormat::Css => {
+                let _ = writeln!(out, ":root {{");
+                for &(step, rgb) in &self.steps {
+                    let _ = writeln!(out, "  --{}-{}: {};", name, step, to_hex(rgb));
+                }
+                let _ = writeln!(out, "}}");
+            },
+            ExportFormat::Json => {
+                let entries: Vec<String> =
+                    self.steps.iter().map(|&(step, rgb)| format!("    \"{}\": \"{}\"", step, to_hex(rgb))).collect();
+                let _ = writeln!(out, "{{\n  \"{}\": {{\n{}\n  }}\n}}", name, entries.join(",\n"));
+            },
+            _ => return None,
+        }
+        Some(out)
+    }
+
+    // one labeled block per step, the input marked
+    pub fn preview(&self, width: u8) {
+        let mut buf = String::new();
+        for (i, &(step, (r, g, b))) in self.steps.iter().enumerate() {
+            let color = ColorValue::Rgb(r, g, b);
+            push_background(&mut buf, color);
+            push_contrast_text(&mut buf, color);
+            let _ = write!(buf, "{:^width$}", step, width = (width as usize).max(5));
+            let _ = writeln!(buf, "\x1b[0m {}{}", to_hex((r, g, b)), if i == self.base_index { "  ◀ input" } else { "" });
+        }
+        print!("{}", buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_input_and_darkens_monotonically() {
+        let base = (59, 130, 246);
+        let ramp = Ramp::generate(base, 11);
+        let names: Vec<u16> = ramp.steps.iter().map(|s| s.0).collect();
+        assert_eq!(names, TAILWIND_STEPS);
+        assert_eq!(ramp.steps[ramp.base_index].1, base);
+
+        let lightness: Vec<f32> = ramp.steps.iter().map(|&(_, (r, g, b))| Oklch::from_rgb(r, g, b).l).collect();
+        assert!(lightness.windows(2).all(|w| w[1] < w[0]));
+        assert!((lightness[0] - LIGHTEST).abs() < 0.01 && (lightness[10] - DARKEST).abs() < 0.01);
+    }
+
+    #[test]
+    fn other_step_counts_get_even_names() {
+        assert_eq!(step_names(9), [100, 200, 300, 400, 500, 600, 700, 800, 900]);
+        assert_eq!(step_names(5), [170, 330, 500, 670, 830]);
+    }
+}
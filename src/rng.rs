@@ -5,15 +5,28 @@ pub struct SimpleRng {
     state: u64,
 }
 
+// seed taken from the system clock when none is given
+pub fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
 impl SimpleRng {
     pub fn new() -> Self {
-        let seed = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64;
-        
+        Self::with_seed(clock_seed())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        // scramble with splitmix64 so nearby seeds (0, 1, 2...) don't start out correlated
+        let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+
         Self { 
-            state: if seed == 0 { 1 } else { seed } 
+            state: if z == 0 { 1 } else { z } 
         }
     }
     
@@ -46,4 +59,32 @@ impl Default for SimpleRng {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_pins_sequence() {
+        let mut rng = SimpleRng::with_seed(42);
+        let values: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(values, [18108192690585582856, 6830302529404445810, 7514410519785295290]);
+    }
+
+    #[test]
+    fn same_seed_same_bytes() {
+        let (mut a, mut b) = (SimpleRng::with_seed(7), SimpleRng::with_seed(7));
+        let (mut x, mut y) = ([0u8; 64], [0u8; 64]);
+        a.fill(&mut x);
+        b.fill(&mut y);
+        assert_eq!(x, y);
+        assert_ne!(SimpleRng::with_seed(8).next_u64(), SimpleRng::with_seed(7).next_u64());
+    }
+
+    #[test]
+    fn zero_seed_is_usable() {
+        let mut rng = SimpleRng::with_seed(0);
+        assert_ne!(rng.next_u64(), rng.next_u64());
+    }
+}
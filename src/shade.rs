@@ -0,0 +1,182 @@
+use crate::{
+    ansi::{ansi_to_rgb, push_color, ColorDepth},
+    color::srgb_to_linear,
+    view::Dither,
+};
+
+// Gradients on terminals without truecolor. Each cell mixes two palette colors with a
+// shade character, so a gradient gets five steps between neighbouring palette entries
+// instead of one flat band per entry
+
+// characters drawing the foreground over 0, 1/4, 2/4 and 3/4 of a cell; a full cell is
+// a space on the foreground color
+const SHADES: [char; 4] = [' ', '░', '▒', '▓'];
+// palette entries nearest a color that are tried as mixing pairs
+const CANDIDATES: usize = 8;
+// 1D Bayer sequence, thresholds 0..8
+const BAYER: [u8; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
+
+type Linear = [f32; 3];
+
+fn linear((r, g, b): (u8, u8, u8)) -> Linear {
+    [r, g, b].map(|v| srgb_to_linear(v as f32 / 255.0))
+}
+
+fn distance_sq(a: Linear, b: Linear) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+fn mix(bg: Linear, fg: Linear, level: usize) -> Linear {
+    let t = level as f32 / 4.0;
+    [0, 1, 2].map(|c| bg[c] + (fg[c] - bg[c]) * t)
+}
+
+// a cell: `fg` covering `level` quarters of it over `bg`, both palette codes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub bg: u8,
+    pub fg: u8,
+    pub level: usize,
+}
+
+// the colors a terminal draws at `depth`, the way `push_color` picks them: the cube and
+// grayscale ramp at 256 (the system colors are often themed), the system colors at 16
+fn palette(depth: ColorDepth) -> Vec<(u8, Linear)> {
+    let codes = if depth == ColorDepth::Ansi16 { 0..=15 } else { 16..=255 };
+    codes.map(|code| (code, linear(ansi_to_rgb(code)))).collect()
+}
+
+struct Mixer {
+    palette: Vec<(u8, Linear)>,
+}
+
+impl Mixer {
+    fn nearest(&self, target: Linear) -> Vec<(u8, Linear)> {
+        let mut sorted = self.palette.clone();
+        sorted.sort_by(|a, b| distance_sq(a.1, target).total_cmp(&distance_sq(b.1, target)));
+        sorted.truncate(CANDIDATES);
+        sorted
+    }
+
+    // the closest of every pair of nearby colors at every shade level
+    fn best(&self, target: Linear) -> (Cell, Linear) {
+        let near = self.nearest(target);
+        let mut best = (Cell { bg: near[0].0, fg: near[0].0, level: 0 }, near[0].1);
+        for &(bg, bg_color) in &near {
+            for &(fg, fg_color) in &near {
+                for level in 1..4 {
+                    let mixed = mix(bg_color, fg_color, level);
+                    if distance_sq(mixed, target) < distance_sq(best.1, target) {
+                        best = (Cell { bg, fg, level }, mixed);
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    // the pair whose mixes pass closest to `target`, and how far along from `bg` to `fg` it lies
+    fn pair(&self, target: Linear) -> (u8, u8, f32) {
+        let near = self.nearest(target);
+        let mut best = (near[0].0, near[0].0, 0.0, f32::MAX);
+        for (i, &(bg, a)) in near.iter().enumerate() {
+            for &(fg, b) in &near[i + 1..] {
+                let ab = [0, 1, 2].map(|c| b[c] - a[c]);
+                let length = ab.iter().map(|v| v * v).sum::<f32>();
+                let t = ((0..3).map(|c| (target[c] - a[c]) * ab[c]).sum::<f32>() / length).clamp(0.0, 1.0);
+                let error = distance_sq([0, 1, 2].map(|c| a[c] + ab[c] * t), target);
+                if error < best.3 {
+                    best = (bg, fg, t, error);
+                }
+            }
+        }
+        (best.0, best.1, best.2)
+    }
+}
+
+// one cell per color, as close as `depth` can get with `dither`
+pub fn shade_cells(colors: &[(u8, u8, u8)], depth: ColorDepth, dither: Dither) -> Vec<Cell> {
+    let mixer = Mixer { palette: palette(depth) };
+    let mut error = [0.0; 3];
+    colors
+        .iter()
+        .enumerate()
+        .map(|(x, &color)| match dither {
+            Dither::None => {
+                let near = mixer.nearest(linear(color))[0].0;
+                Cell { bg: near, fg: near, level: 0 }
+            },
+            // the mixing ratio rounded up or down by a threshold that varies along the row
+            Dither::Ordered => {
+                let (bg, fg, t) = mixer.pair(linear(color));
+                let threshold = (BAYER[x % 8] as f32 + 0.5) / 8.0;
+                Cell { bg, fg, level: ((t * 4.0 + threshold) as usize).min(4) }
+            },
+            // the best mix for the color plus what earlier cells missed by
+            Dither::FloydSteinberg => {
+                let target = linear(color);
+                let wanted = [0, 1, 2].map(|c| (target[c] + error[c]).clamp(0.0, 1.0));
+                let (cell, drawn) = mixer.best(wanted);
+                error = [0, 1, 2].map(|c| wanted[c] - drawn[c]);
+                cell
+            },
+        })
+        .collect()
+}
+
+// append a run of colors as cells; truecolor writes them as they are
+pub fn push_gradient(buf: &mut String, colors: &[(u8, u8, u8)], depth: ColorDepth, dither: Dither) {
+    if depth == ColorDepth::TrueColor {
+        for &(r, g, b) in colors {
+            push_color(buf, depth, false, r, g, b);
+            buf.push(' ');
+        }
+        return;
+    }
+
+    for cell in shade_cells(colors, depth, dither) {
+        let (bg, fg) = (ansi_to_rgb(cell.bg), ansi_to_rgb(cell.fg));
+        match cell.level {
+            0 => push_color(buf, depth, false, bg.0, bg.1, bg.2),
+            4 => push_color(buf, depth, false, fg.0, fg.1, fg.2),
+            _ => {
+                push_color(buf, depth, false, bg.0, bg.1, bg.2);
+                push_color(buf, depth, true, fg.0, fg.1, fg.2);
+            },
+        }
+        buf.push(SHADES[cell.level % 4]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{linear_to_srgb, to_u8};
+
+    #[test]
+    fn mixes_neighbouring_palette_colors() {
+        // halfway (in light) between cube levels 0 and 95 is drawn as a 50% shade of the two
+        let half = (to_u8(linear_to_srgb(linear((95, 0, 0))[0] / 2.0)), 0, 0);
+        let [cell] = shade_cells(&[half], ColorDepth::Ansi256, Dither::FloydSteinberg)[..] else { panic!() };
+        assert_eq!(cell.level, 2);
+        assert_eq!([cell.bg.min(cell.fg), cell.bg.max(cell.fg)], [16, 52]);
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_light() {
+        let gray = (100, 100, 100);
+        let target = linear(gray)[0];
+        for dither in [Dither::Ordered, Dither::FloydSteinberg] {
+            let cells = shade_cells(&[gray; 64], ColorDepth::Ansi16, dither);
+            let mean = cells
+                .iter()
+                .map(|c| mix(linear(ansi_to_rgb(c.bg)), linear(ansi_to_rgb(c.fg)), c.level)[0])
+                .sum::<f32>()
+                / cells.len() as f32;
+            assert!((mean - target).abs() < 0.01, "{}", mean);
+        }
+        // without dithering the whole run is one flat color
+        let flat = shade_cells(&[gray; 8], ColorDepth::Ansi16, Dither::None);
+        assert!(flat.iter().all(|&c| c == flat[0]));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    OnceLock,
+};
+
+use crate::color::{linear_to_srgb, srgb_to_linear, to_u8};
+
+//////////////////////////////////////////////////////////////////////////////////////////
+// Color vision deficiency simulation for --simulate
+
+// Machado, Oliveira & Fernandes (2009) at full severity, applied to linear RGB
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: [[f32; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+// no color at all: every channel becomes the luminance
+const ACHROMATOPSIA: [[f32; 3]; 3] = [
+    [0.2126, 0.7152, 0.0722],
+    [0.2126, 0.7152, 0.0722],
+    [0.2126, 0.7152, 0.0722],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    Achromatopsia,
+}
+
+impl Deficiency {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "protanopia" | "protan" => Some(Self::Protanopia),
+            "deuteranopia" | "deutan" => Some(Self::Deuteranopia),
+            "tritanopia" | "tritan" => Some(Self::Tritanopia),
+            "achromatopsia" | "achroma" => Some(Self::Achromatopsia),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Protanopia => "protanopia",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Tritanopia => "tritanopia",
+            Self::Achromatopsia => "achromatopsia",
+        }
+    }
+
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Self::Protanopia => PROTANOPIA,
+            Self::Deuteranopia => DEUTERANOPIA,
+            Self::Tritanopia => TRITANOPIA,
+            Self::Achromatopsia => ACHROMATOPSIA,
+        }
+    }
+}
+
+pub struct Simulation {
+    matrix: [[f32; 3]; 3],
+    linear: [f32; 256], // sRGB byte to linear light
+}
+
+impl Simulation {
+    // partial severities blend the full matrix with normal vision
+    pub fn new(deficiency: Deficiency, severity: f32) -> Self {
+        let severity = severity.clamp(0.0, 1.0);
+        let full = deficiency.matrix();
+        let matrix = std::array::from_fn(|row| {
+            std::array::from_fn(|col| {
+                let identity = if row == col { 1.0 } else { 0.0 };
+                identity + (full[row][col] - identity) * severity
+            })
+        });
+        Self { matrix, linear: std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)) }
+    }
+
+    pub fn apply(&self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let rgb = [self.linear[r as usize], self.linear[g as usize], self.linear[b as usize]];
+        let channel = |row: [f32; 3]| {
+            let v = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            to_u8(linear_to_srgb(v.clamp(0.0, 1.0)))
+        };
+        (channel(self.matrix[0]), channel(self.matrix[1]), channel(self.matrix[2]))
+    }
+}
+
+static SIMULATION: OnceLock<Simulation> = OnceLock::new();
+// lets --compare draw the same output with and without the simulation
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// turn the simulation on for everything drawn from now on
+pub fn install(simulation: Simulation) {
+    let _ = SIMULATION.set(simulation);
+    set_active(true);
+}
+
+pub fn set_active(active: bool) {
+    ACTIVE.store(active, Ordering::Relaxed);
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed) && SIMULATION.get().is_some()
+}
+
+// `rgb` as it appears under the installed simulation, if one is active
+pub fn apply(rgb: (u8, u8, u8)) -> (u8, u8, u8) {
+    match SIMULATION.get() {
+        Some(simulation) if ACTIVE.load(Ordering::Relaxed) => simulation.apply(rgb),
+        _ => rgb,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grays_survive_and_red_green_collapse() {
+        for deficiency in [Deficiency::Protanopia, Deficiency::Deuteranopia, Deficiency::Tritanopia] {
+            let sim = Simulation::new(deficiency, 1.0);
+            for v in [0, 128, 255] {
+                let (r, g, b) = sim.apply((v, v, v));
+                assert!(r.abs_diff(v) <= 2 && g.abs_diff(v) <= 2 && b.abs_diff(v) <= 2);
+            }
+        }
+
+        let deutan = Simulation::new(Deficiency::Deuteranopia, 1.0);
+        let (red, green) = (deutan.apply((200, 60, 40)), deutan.apply((90, 140, 40)));
+        assert!(red.0.abs_diff(green.0) < 40 && red.1.abs_diff(green.1) < 40);
+    }
+
+    #[test]
+    fn zero_severity_is_normal_vision() {
+        let sim = Simulation::new(Deficiency::Protanopia, 0.0);
+        assert_eq!(sim.apply((12, 200, 99)), (12, 200, 99));
+    }
+}
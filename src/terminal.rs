@@ -1,13 +1,22 @@
 use std::io::{self, Read, Write};
+use std::sync::OnceLock;
 use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
+use std::time::{Duration, Instant};
 use std::mem;
 
 use std::os::unix::io::RawFd;
 
+const STDIN_FILENO: RawFd = 0;
 const STDOUT_FILENO: RawFd = 1;
 const TIOCGWINSZ: libc::c_ulong = 0x5413;
 
+// how long to wait for the rest of an escape sequence before treating ESC as a key
+const ESC_TIMEOUT: Duration = Duration::from_millis(10);
+
+// terminal settings to restore on exit; written once before a signal handler can read them
+static ORIGINAL_TERMIOS: OnceLock<libc::termios> = OnceLock::new();
+
 // apparently this can only be done with libc
 #[repr(C)]
 struct TermSize {
@@ -17,7 +26,7 @@ struct TermSize {
     y: libc::c_ushort,
 }
 
-pub fn terminal_size() -> io::Result<(u16, u16)> {
+fn window_size() -> io::Result<TermSize> {
     // shamelessly "borrowed" from termion
     unsafe {
         let mut size: TermSize = mem::zeroed();
@@ -27,25 +36,164 @@ pub fn terminal_size() -> io::Result<(u16, u16)> {
             return Err(io::Error::last_os_error());
         }
         
-        Ok((size.col as u16, size.row as u16))
+        Ok(size)
+    }
+}
+
+pub fn terminal_size() -> io::Result<(u16, u16)> {
+    window_size().map(|size| (size.col, size.row))
+}
+
+// width and height of a character cell in pixels, when the terminal reports them
+pub fn cell_size() -> Option<(u16, u16)> {
+    let size = window_size().ok()?;
+    if size.x == 0 || size.y == 0 || size.col == 0 || size.row == 0 {
+        return None;
+    }
+    Some((size.x / size.col, size.y / size.row))
+}
+
+// height over width of a character cell
+pub fn cell_aspect() -> Option<f32> {
+    cell_size().map(|(w, h)| h as f32 / w as f32)
+}
+
+// write `request` and collect what the terminal answers until `done` accepts the reply
+// or `timeout` passes; None when stdin or stdout isn't a terminal
+pub fn query(request: &str, timeout: Duration, done: impl Fn(&[u8]) -> bool) -> Option<Vec<u8>> {
+    unsafe {
+        if libc::isatty(STDIN_FILENO) != 1 || libc::isatty(STDOUT_FILENO) != 1 {
+            return None;
+        }
+        let mut original: libc::termios = mem::zeroed();
+        if libc::tcgetattr(STDIN_FILENO, &mut original) < 0 {
+            return None;
+        }
+        // the reply arrives on stdin like typed input, so don't echo it or wait for a newline
+        let mut termios = original;
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+        termios.c_cc[libc::VMIN] = 0;
+        termios.c_cc[libc::VTIME] = 0;
+        if libc::tcsetattr(STDIN_FILENO, libc::TCSANOW, &termios) < 0 {
+            return None;
+        }
+
+        print!("{}", request);
+        let _ = io::stdout().flush();
+
+        let deadline = Instant::now() + timeout;
+        let mut reply = Vec::new();
+        let mut buf = [0u8; 256];
+        while !done(&reply) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let mut fd = libc::pollfd { fd: STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+            if left.is_zero() || libc::poll(&mut fd, 1, left.as_millis().max(1) as libc::c_int) <= 0 {
+                break;
+            }
+            let n = libc::read(STDIN_FILENO, buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+            if n <= 0 {
+                break;
+            }
+            reply.extend_from_slice(&buf[..n as usize]);
+        }
+
+        libc::tcsetattr(STDIN_FILENO, libc::TCSANOW, &original);
+        Some(reply)
+    }
+}
+
+fn save_termios() {
+    unsafe {
+        if libc::isatty(STDIN_FILENO) == 1 {
+            let mut termios: libc::termios = mem::zeroed();
+            if libc::tcgetattr(STDIN_FILENO, &mut termios) == 0 {
+                let _ = ORIGINAL_TERMIOS.set(termios);
+            }
+        }
+    }
+}
+
+// restore the terminal from `handler` when interrupted or terminated mid-animation;
+// the handler may only use `restore_from_signal` and `write_raw`
+pub fn set_signal_handler(handler: extern "C" fn(libc::c_int)) {
+    save_termios();
+    unsafe {
+        libc::signal(libc::SIGINT, handler as *const () as usize);
+        libc::signal(libc::SIGTERM, handler as *const () as usize);
     }
 }
 
 pub fn enable_raw_mode() -> io::Result<()> {
+    save_termios();
+    // turn off line buffering and echo so keys arrive as they are pressed,
+    // signals stay enabled so Ctrl-C still goes through the signal handler
+    unsafe {
+        if libc::isatty(STDIN_FILENO) == 1 {
+            let mut termios: libc::termios = mem::zeroed();
+            if libc::tcgetattr(STDIN_FILENO, &mut termios) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(STDIN_FILENO, libc::TCSANOW, &termios) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+
     print!("\x1b[?25l"); // hide cursor
     io::stdout().flush()
 }
 
 pub fn disable_raw_mode() -> io::Result<()> {
+    if let Some(termios) = ORIGINAL_TERMIOS.get() {
+        unsafe {
+            libc::tcsetattr(STDIN_FILENO, libc::TCSANOW, termios);
+        }
+    }
+
     print!("\x1b[0m\x1b[?25h"); // reset attributes + show cursor
     io::stdout().flush()
 }
 
+// `disable_raw_mode` for signal handlers: no locks and no buffered output, which the
+// interrupted thread may be holding
+pub fn restore_from_signal() {
+    if let Some(termios) = ORIGINAL_TERMIOS.get() {
+        unsafe {
+            libc::tcsetattr(STDIN_FILENO, libc::TCSANOW, termios);
+        }
+    }
+    write_raw(STDOUT_FILENO, b"\x1b[0m\x1b[?25h");
+}
+
+// unbuffered write straight to `fd`, safe to call from a signal handler
+pub fn write_raw(fd: RawFd, bytes: &[u8]) {
+    unsafe {
+        libc::write(fd, bytes.as_ptr() as *const libc::c_void, bytes.len());
+    }
+}
+
 pub fn clear_screen() -> io::Result<()> {
     print!("\x1b[H\x1b[2J"); // cursor home + clear screen
     io::stdout().flush()
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    CtrlC,
+    Unknown, // escape sequences and control bytes we don't handle
+}
+
 // threaded input handler for non-blocking input
 pub struct InputHandler {
     receiver: Receiver<u8>,
@@ -59,14 +207,10 @@ impl InputHandler {
             let mut stdin = io::stdin();
             let mut buffer = [0u8; 1];
             
-            loop {
-                match stdin.read_exact(&mut buffer) {
-                    Ok(_) => {
-                        if sender.send(buffer[0]).is_err() {
-                            break; // Receiver dropped, exit thread
-                        }
-                    }
-                    Err(_) => break, // Error reading, exit thread
+            // exits on read error
+            while stdin.read_exact(&mut buffer).is_ok() {
+                if sender.send(buffer[0]).is_err() {
+                    break; // Receiver dropped, exit thread
                 }
             }
         });
@@ -82,15 +226,66 @@ impl InputHandler {
         }
     }
     
-    pub fn check_exit(&self) -> bool {
+    // bytes of a sequence are sent together, so only wait a moment for them
+    fn read_pending(&self) -> Option<u8> {
+        self.receiver.recv_timeout(ESC_TIMEOUT).ok()
+    }
+
+    // all keys pressed since the last call
+    pub fn read_keys(&self) -> Vec<Key> {
+        let mut keys = Vec::new();
         while let Some(byte) = self.try_read() {
-            match byte {
-                3 => return true,        // Ctrl-C
-                27 => return true,       // Escape
-                b'q' | b'Q' => return true, // 'q' or 'Q'
-                _ => {}
+            keys.push(self.parse_key(byte));
+        }
+        keys
+    }
+
+    fn parse_key(&self, byte: u8) -> Key {
+        match byte {
+            3 => Key::CtrlC,
+            b'\r' | b'\n' => Key::Enter,
+            27 => self.parse_escape(),
+            0x20..=0x7e => Key::Char(byte as char),
+            0xc0..=0xf7 => self.parse_utf8(byte),
+            _ => Key::Unknown,
+        }
+    }
+
+    fn parse_escape(&self) -> Key {
+        match self.read_pending() {
+            None => Key::Escape,
+            // CSI (ESC [) and SS3 (ESC O): parameter bytes, then a final byte in 0x40..=0x7e
+            Some(b'[') | Some(b'O') => loop {
+                match self.read_pending() {
+                    Some(b'A') => return Key::Up,
+                    Some(b'B') => return Key::Down,
+                    Some(b'C') => return Key::Right,
+                    Some(b'D') => return Key::Left,
+                    Some(0x40..=0x7e) | None => return Key::Unknown,
+                    Some(_) => continue,
+                }
+            },
+            Some(27) => Key::Escape,
+            Some(_) => Key::Unknown, // Alt + key
+        }
+    }
+
+    fn parse_utf8(&self, first: u8) -> Key {
+        let len = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let mut bytes = vec![first];
+        for _ in 1..len {
+            match self.read_pending() {
+                Some(b) => bytes.push(b),
+                None => return Key::Unknown,
             }
         }
-        false
+        match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+            Some(c) => Key::Char(c),
+            None => Key::Unknown,
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+use std::fmt::Write as _;
+
+use crate::{
+    ansi::{ansi_name, ansi_to_rgb, rgb_sgr},
+    color::{contrast_ratio, to_hex, ColorValue, Oklch},
+    export::ExportFormat,
+    palette::{print_palette_grid, GridOptions, LabelKind},
+};
+
+// chroma of generated colors when no accent sets it
+const DEFAULT_CHROMA: f32 = 0.14;
+// how much more chroma bright colors get over normal ones
+const BRIGHT_CHROMA: f32 = 0.02;
+// lightness step when nudging a color to reach the contrast target
+const LIGHTNESS_STEP: f32 = 0.01;
+
+pub struct Theme {
+    pub background: (u8, u8, u8),
+    pub foreground: (u8, u8, u8),
+    pub colors: [(u8, u8, u8); 16],
+}
+
+// move `color` away from the background's lightness until it reaches `min_contrast`
+// (or runs out of room)
+fn fit_contrast(mut color: Oklch, background: (u8, u8, u8), min_contrast: f32, dark: bool) -> (u8, u8, u8) {
+    loop {
+        let rgb = color.to_rgb();
+        let at_limit = if dark { color.l >= 1.0 } else { color.l <= 0.0 };
+        if contrast_ratio(rgb, background) >= min_contrast || at_limit {
+            return rgb;
+        }
+        color.l = (color.l + if dark { LIGHTNESS_STEP } else { -LIGHTNESS_STEP }).clamp(0.0, 1.0);
+    }
+}
+
+impl Theme {
+    // the 16 ANSI slots for a background: the six hues aim at the hue of xterm's
+    // color of the same name, and every color except black is pushed to
+    // `min_contrast` against the background. An accent sets the chroma and takes
+    // over the hue of the slot closest to it
+    pub fn generate(background: (u8, u8, u8), accent: Option<(u8, u8, u8)>, min_contrast: f32) -> Self {
+        let bg = Oklch::from_rgb(background.0, background.1, background.2);
+        let dark = bg.l < 0.5;
+
+        let mut hues: [f32; 6] = std::array::from_fn(|i| {
+            let (r, g, b) = ansi_to_rgb(i as u8 + 1);
+            Oklch::from_rgb(r, g, b).h
+        });
+        let mut chroma = DEFAULT_CHROMA;
+        if let Some((r, g, b)) = accent {
+            let accent = Oklch::from_rgb(r, g, b);
+            let distance = |h: f32| {
+                let d = (h - accent.h).rem_euclid(360.0);
+                d.min(360.0 - d)
+            };
+            if let Some(closest) = (0..6).min_by(|&a, &b| distance(hues[a]).total_cmp(&distance(hues[b]))) {
+                hues[closest] = accent.h;
+            }
+            chroma = accent.c.clamp(0.06, 0.25);
+        }
+
+        // grays carry a trace of the background's hue
+        let gray = |l: f32| Oklch { l, c: bg.c.min(0.02), h: bg.h };
+        let (normal_l, bright_l) = if dark { (0.68, 0.78) } else { (0.52, 0.44) };
+        let mut colors = [(0, 0, 0); 16];
+
+        for (i, &h) in hues.iter().enumerate() {
+            colors[i + 1] = fit_contrast(Oklch { l: normal_l, c: chroma, h }, background, min_contrast, dark);
+            colors[i + 9] =
+                fit_contrast(Oklch { l: bright_l, c: chroma + BRIGHT_CHROMA, h }, background, min_contrast, dark);
+        }
+
+        // black sits just off the background on dark themes; white does on light ones
+        if dark {
+            colors[0] = gray(bg.l + 0.08).to_rgb();
+            colors[8] = fit_contrast(gray(bg.l + 0.3), background, min_contrast.min(3.0), dark);
+            colors[7] = fit_contrast(gray(0.85), background, min_contrast, dark);
+            colors[15] = gray(0.97).to_rgb();
+        } else {
+            colors[0] = fit_contrast(gray(0.25), background, min_contrast, dark);
+            colors[8] = fit_contrast(gray(0.5), background, min_contrast.min(3.0), dark);
+            colors[7] = gray(bg.l - 0.12).to_rgb();
+            colors[15] = gray(bg.l - 0.04).to_rgb();
+        }
+
+        let foreground = fit_contrast(gray(if dark { 0.9 } else { 0.22 }), background, min_contrast.max(7.0), dark);
+        Self { background, foreground, colors }
+    }
+
+    // the theme as a config snippet for a terminal emulator, if `format` is one
+    pub fn export(&self, format: ExportFormat) -> Option<String> {
+        let mut out = String::new();
+        match format {
+            ExportFormat::Kitty => {
+                let _ = writeln!(out, "background {}", to_hex(self.background));
+                let _ = writeln!(out, "foreground {}", to_hex(self.foreground));
+                let _ = writeln!(out, "cursor {}", to_hex(self.foreground));
+                for (i, &c) in self.colors.iter().enumerate() {
+                    let _ = writeln!(out, "color{} {}", i, to_hex(c));
+                }
+            },
+            ExportFormat::Alacritty => {
+                let _ = writeln!(out, "[colors.primary]");
+                let _ = writeln!(out, "background = \"{}\"", to_hex(self.background));
+                let _ = writeln!(out, "foreground = \"{}\"", to_hex(self.foreground));
+                for (section, offset) in [("normal", 0), ("bright", 8)] {
+                    let _ = writeln!(out, "\n[colors.{}]", section);
+                    for i in 0..8 {
+                        let name = ansi_name(i).unwrap_or_default();
+                        let _ = writeln!(out, "{} = \"{}\"", name, to_hex(self.colors[(i + offset) as usize]));
+                    }
+                }
+            },
+            ExportFormat::Xresources => {
+                let _ = writeln!(out, "*.background: {}", to_hex(self.background));
+                let _ = writeln!(out, "*.foreground: {}", to_hex(self.foreground));
+                let _ = writeln!(out, "*.cursorColor: {}", to_hex(self.foreground));
+                for (i, &c) in self.colors.iter().enumerate() {
+                    let _ = writeln!(out, "*.color{}: {}", i, to_hex(c));
+                }
+            },
+            ExportFormat::Css | ExportFormat::Json | ExportFormat::Hex | ExportFormat::Rgb => return None,
+        }
+        Some(out)
+    }
+
+    // the palette as labeled blocks, then the color names as text on the background
+    pub fn preview(&self, width: u8) {
+        println!("background {}  foreground {}", to_hex(self.background), to_hex(self.foreground));
+        let swatches: Vec<(String, ColorValue)> =
+            self.colors.iter().map(|&(r, g, b)| (String::new(), ColorValue::Rgb(r, g, b))).collect();
+        print_palette_grid(&swatches, &GridOptions { columns: 8, width, height: 1, label: LabelKind::Hex });
+
+        let (br, bg, bb) = self.background;
+        let (fr, fg, fb) = self.foreground;
+        let mut text = String::new();
+        for offset in [0, 8] {
+            let _ = write!(text, "{}{} ", rgb_sgr(false, br, bg, bb), rgb_sgr(true, fr, fg, fb));
+            let _ = write!(text, "{:<7}", if offset == 0 { "normal" } else { "bright" });
+            for i in offset..offset + 8 {
+                let (r, g, b) = self.colors[i as usize];
+                let name = ansi_name(i).unwrap_or_default().trim_start_matches("bright_");
+                let _ = write!(text, "{}{:<8}", rgb_sgr(true, r, g, b), name);
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        print!("{}", text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_meet_contrast_on_dark_and_light_backgrounds() {
+        for background in [(24, 24, 32), (250, 246, 235)] {
+            let theme = Theme::generate(background, None, 4.5);
+            for i in (1..7).chain(9..15) {
+                assert!(contrast_ratio(theme.colors[i], background) >= 4.5, "slot {} on {:?}", i, background);
+            }
+            assert!(contrast_ratio(theme.foreground, background) >= 7.0);
+        }
+    }
+
+    #[test]
+    fn accent_takes_the_nearest_slot() {
+        let accent = (255, 120, 0);
+        let theme = Theme::generate((20, 20, 20), Some(accent), 4.5);
+        let hue = |(r, g, b): (u8, u8, u8)| Oklch::from_rgb(r, g, b).h;
+        // orange is closest to xterm's red
+        assert!((hue(theme.colors[1]) - hue(accent)).abs() < 5.0);
+    }
+}
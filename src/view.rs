@@ -0,0 +1,236 @@
+use std::io::{self, BufWriter, Write};
+
+use crate::{
+    ansi::{ansi_to_rgb, rgb_to_ansi16, rgb_to_ansi256, ColorDepth},
+    color::{linear_to_srgb, srgb_to_linear, to_u8},
+    image::Image,
+    rainbow::{render_rows, PixelBuffer},
+    terminal::{cell_aspect, terminal_size},
+};
+
+// height over width of a cell when the terminal doesn't report its pixel size
+pub const DEFAULT_CELL_ASPECT: f32 = 2.0;
+
+// 8x8 Bayer matrix, thresholds 0..64
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+impl Dither {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "ordered" | "bayer" => Some(Self::Ordered),
+            "floyd" | "floyd-steinberg" | "fs" => Some(Self::FloydSteinberg),
+            _ => None,
+        }
+    }
+}
+
+// source pixels (and how much of each) making up each of `to` pixels spread over `from`
+fn coverage(from: usize, to: usize) -> Vec<Vec<(usize, f32)>> {
+    let scale = from as f32 / to as f32;
+    (0..to)
+        .map(|i| {
+            let (start, end) = (i as f32 * scale, (i + 1) as f32 * scale);
+            (start.floor() as usize..(end.ceil() as usize).min(from))
+                .map(|s| (s, (end.min(s as f32 + 1.0) - start.max(s as f32)) / scale))
+                .filter(|&(_, weight)| weight > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+// `width` x `height` pixels, each the average of the source area it covers; averaged in
+// linear light so fine detail keeps its brightness, with transparency composited over black
+pub fn resize(image: &Image, width: usize, height: usize) -> Vec<[f32; 3]> {
+    let linear: Vec<[f32; 3]> = image
+        .pixels
+        .iter()
+        .map(|p| {
+            let alpha = p[3] as f32 / 255.0;
+            [0, 1, 2].map(|c| srgb_to_linear(p[c] as f32 / 255.0) * alpha)
+        })
+        .collect();
+
+    let (columns, rows) = (coverage(image.width, width), coverage(image.height, height));
+    let mut across = vec![[0.0; 3]; width * image.height];
+    for y in 0..image.height {
+        for (x, sources) in columns.iter().enumerate() {
+            let sum = &mut across[y * width + x];
+            for &(sx, weight) in sources {
+                for c in 0..3 {
+                    sum[c] += linear[y * image.width + sx][c] * weight;
+                }
+            }
+        }
+    }
+
+    let mut out = vec![[0.0; 3]; width * height];
+    for (y, sources) in rows.iter().enumerate() {
+        for x in 0..width {
+            let sum = &mut out[y * width + x];
+            for &(sy, weight) in sources {
+                for c in 0..3 {
+                    sum[c] += across[sy * width + x][c] * weight;
+                }
+            }
+        }
+    }
+    for p in &mut out {
+        *p = p.map(|v| linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0);
+    }
+    out
+}
+
+// cell columns and pixel rows (two per cell with half blocks) that show an image
+// without stretching inside `columns` x `rows` cells; never scales up
+pub fn fit(image: &Image, columns: usize, rows: usize, cell_aspect: f32) -> (usize, usize) {
+    let pixel_aspect = cell_aspect / 2.0; // height over width of half a cell
+    let ratio = image.height as f32 / image.width as f32;
+    let mut width = columns.min(image.width).max(1);
+    let mut height = (width as f32 * ratio / pixel_aspect).round() as usize;
+    if height > rows * 2 {
+        height = rows * 2;
+        width = ((height as f32 * pixel_aspect / ratio).round() as usize).clamp(1, columns);
+    }
+    // whole cells only
+    (width, height.div_ceil(2).max(1) * 2)
+}
+
+// nearest color the terminal will actually draw at `depth`
+fn quantize(depth: ColorDepth, [r, g, b]: [f32; 3]) -> [f32; 3] {
+    let (r, g, b) = (r.clamp(0.0, 255.0) as u8, g.clamp(0.0, 255.0) as u8, b.clamp(0.0, 255.0) as u8);
+    let (r, g, b) = match depth {
+        ColorDepth::TrueColor => (r, g, b),
+        ColorDepth::Ansi256 => ansi_to_rgb(rgb_to_ansi256(r, g, b)),
+        ColorDepth::Ansi16 => ansi_to_rgb(rgb_to_ansi16(r, g, b)),
+    };
+    [r as f32, g as f32, b as f32]
+}
+
+// snap pixels to the colors available at `depth`, spreading the difference with `dither`
+pub fn dither(pixels: &mut [[f32; 3]], width: usize, depth: ColorDepth, dither: Dither) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+    // roughly the gap between neighbouring palette levels: six cube levels per channel
+    // at 256 colors, off and on at 16
+    let spread = if depth == ColorDepth::Ansi256 { 51.0 } else { 128.0 };
+
+    for i in 0..pixels.len() {
+        let (x, y) = (i % width, i / width);
+        let old = pixels[i];
+        match dither {
+            Dither::None => pixels[i] = quantize(depth, old),
+            Dither::Ordered => {
+                let threshold = (BAYER[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5;
+                pixels[i] = quantize(depth, old.map(|v| v + threshold * spread));
+            },
+            Dither::FloydSteinberg => {
+                let new = quantize(depth, old);
+                pixels[i] = new;
+                let error = [0, 1, 2].map(|c| old[c] - new[c]);
+                let mut push = |dx: isize, dy: usize, weight: f32| {
+                    let nx = x as isize + dx;
+                    if nx >= 0 && (nx as usize) < width && let Some(p) = pixels.get_mut((y + dy) * width + nx as usize) {
+                        for c in 0..3 {
+                            p[c] += error[c] * weight;
+                        }
+                    }
+                };
+                push(1, 0, 7.0 / 16.0);
+                push(-1, 1, 3.0 / 16.0);
+                push(0, 1, 5.0 / 16.0);
+                push(1, 1, 1.0 / 16.0);
+            },
+        }
+    }
+}
+
+// an image scaled to fit the terminal and drawn with half blocks
+pub fn print_image(image: &Image, depth: ColorDepth, mode: Dither) {
+    let (columns, rows) = terminal_size().map(|(w, h)| (w as usize, h as usize)).unwrap_or((80, 24));
+    let aspect = cell_aspect().unwrap_or(DEFAULT_CELL_ASPECT);
+    // leave a line for the prompt
+    let (width, height) = fit(image, columns, rows.saturating_sub(1).max(1), aspect);
+
+    let mut pixels = resize(image, width, height);
+    dither(&mut pixels, width, depth, mode);
+    print_pixels(&pixels, width, height, depth);
+}
+
+// `width` x `height` pixels (an even number of rows) drawn with half blocks
+pub fn print_pixels(pixels: &[[f32; 3]], width: usize, height: usize, depth: ColorDepth) {
+    let mut buffer = PixelBuffer::new(width * height);
+    for (i, p) in pixels.iter().enumerate() {
+        buffer.r[i] = to_u8(p[0] / 255.0);
+        buffer.g[i] = to_u8(p[1] / 255.0);
+        buffer.b[i] = to_u8(p[2] / 255.0);
+    }
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let _ = out.write_all(render_rows(&buffer, width, height / 2, depth, true, 0..height / 2).as_bytes());
+    let _ = writeln!(out, "\x1b[0m");
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize, pixels: Vec<[u8; 4]>) -> Image {
+        Image { width, height, pixels }
+    }
+
+    #[test]
+    fn downscales_by_area_in_linear_light() {
+        // a black and white checkerboard averages to half the light, not to sRGB 128
+        let checker = image(2, 2, vec![[0, 0, 0, 255], [255, 255, 255, 255], [255, 255, 255, 255], [0, 0, 0, 255]]);
+        let [r, g, b] = resize(&checker, 1, 1)[0];
+        assert_eq!([r, g, b].map(|v| v.round() as u8), [188, 188, 188]);
+
+        // 3 pixels into 2: the middle one is split evenly
+        let row = image(3, 1, vec![[255, 0, 0, 255], [0, 0, 0, 255], [0, 0, 255, 255]]);
+        let out = resize(&row, 2, 1);
+        assert!(out[0][0] > 200.0 && out[0][2] == 0.0 && out[1][2] > 200.0 && out[1][0] == 0.0);
+    }
+
+    #[test]
+    fn fits_inside_the_terminal_keeping_the_aspect_ratio() {
+        let wide = image(400, 100, Vec::new());
+        assert_eq!(fit(&wide, 80, 24, 2.0), (80, 20));
+        let tall = image(100, 400, Vec::new());
+        assert_eq!(fit(&tall, 80, 24, 2.0), (12, 48));
+        // small images keep their size; taller cells get fewer pixel rows
+        assert_eq!(fit(&image(10, 10, Vec::new()), 80, 24, 2.0), (10, 10));
+        assert_eq!(fit(&image(10, 10, Vec::new()), 80, 24, 2.5), (10, 8));
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_color() {
+        let width = 32;
+        for mode in [Dither::Ordered, Dither::FloydSteinberg] {
+            let mut pixels = vec![[128.0, 128.0, 128.0]; width * width];
+            dither(&mut pixels, width, ColorDepth::Ansi16, mode);
+            let mean = pixels.iter().map(|p| p[0]).sum::<f32>() / pixels.len() as f32;
+            assert!(pixels.iter().all(|p| [0.0, 127.0, 205.0, 229.0, 255.0].contains(&p[0])));
+            assert!((mean - 128.0).abs() < 12.0, "{}", mean);
+        }
+    }
+}